@@ -0,0 +1,107 @@
+#![feature(test)]
+
+extern crate test;
+
+use test::Bencher;
+
+use yolk::{YolkProgram, YololProgram};
+
+use std::convert::TryInto;
+
+/// Transpiles a Yolk source to Yolol.
+fn transpile(source: &str) -> YololProgram {
+    let yolk: YolkProgram = source.parse().unwrap();
+    yolk.try_into().unwrap()
+}
+
+/// Generates a source where every variable can be folded into a literal.
+fn literal_chain(count: usize) -> String {
+    let mut source = String::from("let total = 0\n");
+    for i in 0..count {
+        source.push_str(&format!("set total = total + {}\n", i % 7));
+    }
+    source
+}
+
+/// Generates a source where every variable depends on an import.
+fn import_chain(count: usize) -> String {
+    let mut source = String::from("import a\nlet total = a\n");
+    for i in 0..count {
+        let term = format!("(total + a * {})", i % 5);
+        source.push_str(&format!("set total = {} * ({} + 1)\n", term, term));
+    }
+    source
+}
+
+/// Generates a source with long unrolled arrays.
+fn vector_chain(len: usize, count: usize) -> String {
+    let elements: Vec<String> = (0..len).map(|i| format!("a + {}", i)).collect();
+    let mut source = format!("import a\nlet total = [{}]\n", elements.join(", "));
+    for i in 0..count {
+        source.push_str(&format!("set total = total * 2 - total + {}\n", i % 7));
+    }
+    source
+}
+
+/// Generates a source with one long sum of imported variables.
+fn long_sum(len: usize) -> String {
+    // Yolk identifiers cannot contain digits, so each index is spelled with letters
+    let name = |mut i: usize| {
+        let mut name = String::from("v");
+        loop {
+            name.push((b'a' + (i % 26) as u8) as char);
+            i /= 26;
+            if i == 0 {
+                return name;
+            }
+        }
+    };
+    let names: Vec<String> = (0..len).map(name).collect();
+    let mut source = String::new();
+    for name in names.iter() {
+        source.push_str(&format!("import {}\n", name));
+    }
+    source.push_str(&format!("let total = sum([{}])\n", names.join(", ")));
+    source
+}
+
+/// Generates a source with one sum over a wide array.
+fn wide_sum(len: usize) -> String {
+    format!("import a\nlet total = sum(a * range(0, {}) + 1)\n", len)
+}
+
+#[bench]
+fn bench_literal_chain(b: &mut Bencher) {
+    let yolol = transpile(&literal_chain(2000));
+    b.iter(|| yolol.clone().optimize());
+}
+
+#[bench]
+fn bench_import_chain(b: &mut Bencher) {
+    let yolol = transpile(&import_chain(1000));
+    b.iter(|| yolol.clone().optimize());
+}
+
+#[bench]
+fn bench_vector_chain(b: &mut Bencher) {
+    let yolol = transpile(&vector_chain(100, 20));
+    b.iter(|| yolol.clone().optimize());
+}
+
+#[bench]
+fn bench_transpile_and_optimize(b: &mut Bencher) {
+    let source = vector_chain(100, 20);
+    b.iter(|| transpile(&source).optimize());
+}
+
+#[bench]
+fn bench_long_sum(b: &mut Bencher) {
+    let yolol = transpile(&long_sum(5000));
+    b.iter(|| yolol.clone().optimize());
+}
+
+#[bench]
+fn bench_wide_sum(b: &mut Bencher) {
+    let yolol = transpile(&wide_sum(1000));
+    b.iter(|| yolol.clone().optimize());
+}
@@ -0,0 +1,18 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use yolk::{YolkProgram, YololProgram};
+
+use std::convert::TryFrom;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(source) = std::str::from_utf8(data) {
+        if let Ok(yolk) = source.parse::<YolkProgram>() {
+            let parsed: YolkProgram = yolk.to_string().parse().unwrap();
+            assert_eq!(parsed, yolk);
+            if let Ok(yolol) = YololProgram::try_from(yolk) {
+                yolol.optimize();
+            }
+        }
+    }
+});
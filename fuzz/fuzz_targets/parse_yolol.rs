@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+use yolk::YololProgram;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(source) = std::str::from_utf8(data) {
+        if let Ok(yolol) = source.parse::<YololProgram>() {
+            yolol.optimize();
+        }
+    }
+});
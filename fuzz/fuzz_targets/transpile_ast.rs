@@ -0,0 +1,191 @@
+#![no_main]
+use arbitrary::{Arbitrary, Result, Unstructured};
+use libfuzzer_sys::fuzz_target;
+use yolol_number::YololNumber;
+
+use yolk::ast::{BuiltinOp, FoldOp, InfixOp, PrefixOp, Span, YolkExpr, YolkStmt};
+use yolk::{YolkProgram, YololProgram};
+
+use std::convert::TryFrom;
+
+const MAX_DEPTH: usize = 8;
+const MAX_ARGS: usize = 4;
+const IDENTS: &[&str] = &["a", "b", "c", "A", "sum", "f", "g"];
+
+const PREFIX_OPS: &[PrefixOp] = &[
+    PrefixOp::Neg,
+    PrefixOp::Not,
+    PrefixOp::Abs,
+    PrefixOp::Sqrt,
+    PrefixOp::Sin,
+    PrefixOp::Cos,
+    PrefixOp::Tan,
+    PrefixOp::Asin,
+    PrefixOp::Acos,
+    PrefixOp::Atan,
+];
+
+const FOLD_OPS: &[FoldOp] = &[
+    FoldOp::Sum,
+    FoldOp::Product,
+    FoldOp::Min,
+    FoldOp::Max,
+];
+
+const BUILTIN_OPS: &[BuiltinOp] = &[
+    BuiltinOp::Select,
+    BuiltinOp::Clamp,
+    BuiltinOp::Len,
+    BuiltinOp::Range,
+    BuiltinOp::Fill,
+];
+
+const INFIX_OPS: &[InfixOp] = &[
+    InfixOp::Add,
+    InfixOp::Sub,
+    InfixOp::Mul,
+    InfixOp::Div,
+    InfixOp::Mod,
+    InfixOp::Exp,
+    InfixOp::LessThan,
+    InfixOp::LessEqual,
+    InfixOp::GreaterThan,
+    InfixOp::GreaterEqual,
+    InfixOp::Equal,
+    InfixOp::NotEqual,
+    InfixOp::And,
+    InfixOp::Or,
+];
+
+fn ident(u: &mut Unstructured) -> Result<String> {
+    Ok(u.choose(IDENTS)?.to_string())
+}
+
+fn literal(u: &mut Unstructured) -> Result<YololNumber> {
+    Ok(YololNumber::from_inner(i64::arbitrary(u)?))
+}
+
+fn args(u: &mut Unstructured, depth: usize) -> Result<Vec<YolkExpr>> {
+    let len = u.int_in_range(0..=MAX_ARGS)?;
+    (0..len).map(|_| expr(u, depth + 1)).collect()
+}
+
+fn bound(u: &mut Unstructured, depth: usize) -> Result<Option<Box<YolkExpr>>> {
+    if bool::arbitrary(u)? {
+        Ok(Some(Box::new(expr(u, depth + 1)?)))
+    } else {
+        Ok(None)
+    }
+}
+
+fn expr(u: &mut Unstructured, depth: usize) -> Result<YolkExpr> {
+    let span = Span::default();
+    let choice = if depth >= MAX_DEPTH {
+        u.int_in_range(0..=1)?
+    } else {
+        u.int_in_range(0..=9)?
+    };
+    Ok(match choice {
+        0 => YolkExpr::Ident(ident(u)?, span),
+        1 => YolkExpr::Literal(literal(u)?, span),
+        2 => YolkExpr::Prefix {
+            op: *u.choose(PREFIX_OPS)?,
+            expr: Box::new(expr(u, depth + 1)?),
+            span,
+        },
+        // Folds may have no args, which the parser never produces
+        3 => YolkExpr::Fold {
+            op: *u.choose(FOLD_OPS)?,
+            args: args(u, depth)?,
+            span,
+        },
+        4 => YolkExpr::Call {
+            ident: ident(u)?,
+            args: args(u, depth)?,
+            span,
+        },
+        5 => YolkExpr::Infix {
+            lhs: Box::new(expr(u, depth + 1)?),
+            op: *u.choose(INFIX_OPS)?,
+            rhs: Box::new(expr(u, depth + 1)?),
+            span,
+        },
+        6 => YolkExpr::Builtin {
+            op: *u.choose(BUILTIN_OPS)?,
+            args: args(u, depth)?,
+            span,
+        },
+        7 => YolkExpr::Index {
+            expr: Box::new(expr(u, depth + 1)?),
+            index: Box::new(expr(u, depth + 1)?),
+            span,
+        },
+        8 => YolkExpr::Slice {
+            expr: Box::new(expr(u, depth + 1)?),
+            start: bound(u, depth)?,
+            end: bound(u, depth)?,
+            span,
+        },
+        _ => YolkExpr::Array(args(u, depth)?, span),
+    })
+}
+
+fn stmt(u: &mut Unstructured) -> Result<YolkStmt> {
+    let span = Span::default();
+    Ok(match u.int_in_range(0..=5)? {
+        0 => YolkStmt::Import {
+            ident: ident(u)?,
+            span,
+        },
+        // Functions may have no params, which the parser never produces
+        1 => YolkStmt::Define {
+            ident: ident(u)?,
+            params: (0..u.int_in_range(0..=MAX_ARGS)?)
+                .map(|_| ident(u))
+                .collect::<Result<Vec<String>>>()?,
+            body: Box::new(expr(u, 0)?),
+            span,
+        },
+        2 => YolkStmt::Set {
+            ident: ident(u)?,
+            expr: Box::new(expr(u, 0)?),
+            span,
+        },
+        // Use statements are only resolved by the loader
+        3 => YolkStmt::Use {
+            path: ident(u)?,
+            span,
+        },
+        4 => YolkStmt::Export {
+            ident: ident(u)?,
+            alias: if bool::arbitrary(u)? {
+                Some(ident(u)?)
+            } else {
+                None
+            },
+            span,
+        },
+        _ => YolkStmt::Let {
+            ident: ident(u)?,
+            expr: Box::new(expr(u, 0)?),
+            span,
+        },
+    })
+}
+
+fn program(u: &mut Unstructured) -> Result<YolkProgram> {
+    let mut stmts = Vec::new();
+    while !u.is_empty() {
+        stmts.push(stmt(u)?);
+    }
+    Ok(stmts.into())
+}
+
+fuzz_target!(|data: &[u8]| {
+    let mut u = Unstructured::new(data);
+    if let Ok(yolk) = program(&mut u) {
+        if let Ok(yolol) = YololProgram::try_from(yolk) {
+            yolol.optimize();
+        }
+    }
+});
@@ -1,12 +1,15 @@
+use std::collections::HashSet;
 use std::convert::TryFrom;
 use std::fmt;
 use std::str::FromStr;
 
 use yolol_number::YololNumber;
 
-use crate::error::YolkError;
-use crate::optimizer::optimize;
-use crate::parser::parse;
+use crate::error::{LayoutError, YolkError};
+use crate::layout::{layout, pack, partition, ChipLayout, ChipLimits, Partition};
+use crate::minifier::{minify, NameMap};
+use crate::optimizer::{optimize, optimize_with_roots};
+use crate::parser::{parse, parse_yolol};
 use crate::transpiler::transpile;
 
 #[cfg(test)]
@@ -21,12 +24,12 @@ pub struct YolkProgram {
 impl From<Vec<YolkStmt>> for YolkProgram {
     /// Converts Yolk statements to a program.
     fn from(stmts: Vec<YolkStmt>) -> Self {
-        YolkProgram { stmts: stmts }
+        YolkProgram { stmts }
     }
 }
 
 impl FromStr for YolkProgram {
-    type Err = YolkError;
+    type Err = Vec<YolkError>;
 
     /// Parses a Yolk program from a string.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -34,6 +37,14 @@ impl FromStr for YolkProgram {
     }
 }
 
+impl fmt::Display for YolkProgram {
+    /// Formats a Yolk program as a string.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<String> = self.stmts.iter().map(|s| s.to_string()).collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
 impl IntoIterator for YolkProgram {
     type Item = YolkStmt;
     type IntoIter = std::vec::IntoIter<Self::Item>;
@@ -49,41 +60,323 @@ impl IntoIterator for YolkProgram {
 pub enum YolkStmt {
     Import {
         ident: String,
+        span: Span,
     },
     Define {
         ident: String,
         params: Vec<String>,
         body: Box<YolkExpr>,
+        span: Span,
     },
     Let {
         ident: String,
         expr: Box<YolkExpr>,
+        span: Span,
+    },
+    Set {
+        ident: String,
+        expr: Box<YolkExpr>,
+        span: Span,
+    },
+    Use {
+        path: String,
+        span: Span,
+    },
+    Export {
+        ident: String,
+        alias: Option<String>,
+        span: Span,
     },
 }
 
+impl YolkStmt {
+    /// Returns the span of a Yolk statement.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Import { span, .. }
+            | Self::Define { span, .. }
+            | Self::Let { span, .. }
+            | Self::Set { span, .. }
+            | Self::Use { span, .. }
+            | Self::Export { span, .. } => *span,
+        }
+    }
+}
+
+impl fmt::Display for YolkStmt {
+    /// Formats a Yolk statement as a string.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Import { ident, .. } => write!(f, "import {}", ident),
+            Self::Define {
+                ident,
+                params,
+                body,
+                ..
+            } => write!(f, "define {}({}) = {}", ident, params.join(", "), body),
+            Self::Let { ident, expr, .. } => write!(f, "let {} = {}", ident, expr),
+            Self::Set { ident, expr, .. } => write!(f, "set {} = {}", ident, expr),
+            Self::Use { path, .. } => write!(f, "use \"{}\"", path),
+            Self::Export { ident, alias, .. } => match alias {
+                Some(alias) => write!(f, "export {} as {}", ident, alias),
+                None => write!(f, "export {}", ident),
+            },
+        }
+    }
+}
+
 /// Represents a Yolk expression.
 #[derive(Debug, Clone, PartialEq)]
 pub enum YolkExpr {
     Prefix {
         op: PrefixOp,
         expr: Box<YolkExpr>,
+        span: Span,
     },
     Fold {
-        op: InfixOp,
+        op: FoldOp,
         args: Vec<YolkExpr>,
+        span: Span,
+    },
+    Builtin {
+        op: BuiltinOp,
+        args: Vec<YolkExpr>,
+        span: Span,
     },
     Call {
         ident: String,
         args: Vec<YolkExpr>,
+        span: Span,
     },
     Infix {
         lhs: Box<YolkExpr>,
         op: InfixOp,
         rhs: Box<YolkExpr>,
+        span: Span,
     },
-    Ident(String),
-    Literal(YololNumber),
-    Array(Vec<YolkExpr>),
+    Index {
+        expr: Box<YolkExpr>,
+        index: Box<YolkExpr>,
+        span: Span,
+    },
+    Slice {
+        expr: Box<YolkExpr>,
+        start: Option<Box<YolkExpr>>,
+        end: Option<Box<YolkExpr>>,
+        span: Span,
+    },
+    Ident(String, Span),
+    Literal(YololNumber, Span),
+    Array(Vec<YolkExpr>, Span),
+}
+
+impl YolkExpr {
+    /// Returns the span of a Yolk expression.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::Prefix { span, .. }
+            | Self::Fold { span, .. }
+            | Self::Builtin { span, .. }
+            | Self::Call { span, .. }
+            | Self::Infix { span, .. }
+            | Self::Index { span, .. }
+            | Self::Slice { span, .. }
+            | Self::Ident(_, span)
+            | Self::Literal(_, span)
+            | Self::Array(_, span) => *span,
+        }
+    }
+
+    /// Returns a mutable reference to the span of a Yolk expression.
+    pub fn span_mut(&mut self) -> &mut Span {
+        match self {
+            Self::Prefix { span, .. }
+            | Self::Fold { span, .. }
+            | Self::Builtin { span, .. }
+            | Self::Call { span, .. }
+            | Self::Infix { span, .. }
+            | Self::Index { span, .. }
+            | Self::Slice { span, .. }
+            | Self::Ident(_, span)
+            | Self::Literal(_, span)
+            | Self::Array(_, span) => span,
+        }
+    }
+
+    /// Applies a function to an expression and each of its subexpressions.
+    pub fn walk_mut(&mut self, f: &mut dyn FnMut(&mut YolkExpr)) {
+        f(self);
+        match self {
+            Self::Prefix { expr, .. } => expr.walk_mut(f),
+            Self::Fold { args, .. }
+            | Self::Builtin { args, .. }
+            | Self::Call { args, .. }
+            | Self::Array(args, _) => {
+                for arg in args.iter_mut() {
+                    arg.walk_mut(f);
+                }
+            }
+            Self::Infix { lhs, rhs, .. }
+            | Self::Index {
+                expr: lhs,
+                index: rhs,
+                ..
+            } => {
+                lhs.walk_mut(f);
+                rhs.walk_mut(f);
+            }
+            Self::Slice {
+                expr, start, end, ..
+            } => {
+                expr.walk_mut(f);
+                for bound in start.iter_mut().chain(end.iter_mut()) {
+                    bound.walk_mut(f);
+                }
+            }
+            Self::Ident(..) | Self::Literal(..) => (),
+        }
+    }
+}
+
+impl fmt::Display for YolkExpr {
+    /// Formats a Yolk expression as a string.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.format(0))
+    }
+}
+
+impl YolkExpr {
+    // Negations bind tighter than infix ops, but looser than indices
+    const NEG_PREC: u32 = 90;
+    const INDEX_PREC: u32 = 100;
+
+    fn format(&self, parent_prec: u32) -> String {
+        match self {
+            Self::Prefix {
+                op: PrefixOp::Neg,
+                expr,
+                ..
+            } => {
+                // Consecutive minus signs are parsed as a chain of negations
+                let mut depth = 1;
+                let mut operand = expr;
+                while let Self::Prefix {
+                    op: PrefixOp::Neg,
+                    expr,
+                    ..
+                } = &**operand
+                {
+                    depth += 1;
+                    operand = expr;
+                }
+                let mut operand = operand.format(Self::NEG_PREC);
+                // A single minus sign before a digit would be parsed as a negative literal,
+                // and a negative literal would be parsed as part of the chain
+                let is_digit = operand.starts_with(|c: char| c.is_ascii_digit());
+                if operand.starts_with('-') || (depth == 1 && is_digit) {
+                    operand = format!("({})", operand);
+                }
+                wrap(
+                    format!("{}{}", "-".repeat(depth), operand),
+                    Self::NEG_PREC < parent_prec,
+                )
+            }
+            Self::Prefix { op, expr, .. } => format!("{}({})", op, expr.format(0)),
+            Self::Fold { op, args, .. } => format!("{}({})", op, format_args(args)),
+            Self::Builtin { op, args, .. } => format!("{}({})", op, format_args(args)),
+            Self::Call { ident, args, .. } => format!("{}({})", ident, format_args(args)),
+            Self::Infix { lhs, op, rhs, .. } => {
+                let prec = op.to_yolk_precedence();
+                // The parser nests exponents to the right and every other op to the left,
+                // so "a+(b+c)" must keep its parentheses to parse back to the same tree
+                let (lhs_prec, rhs_prec) = if *op == InfixOp::Exp {
+                    (prec + 1, prec)
+                } else {
+                    (prec, prec + 1)
+                };
+                wrap(
+                    format!("{} {} {}", lhs.format(lhs_prec), op, rhs.format(rhs_prec)),
+                    prec < parent_prec,
+                )
+            }
+            Self::Index { expr, index, .. } => {
+                format!("{}[{}]", expr.format(Self::INDEX_PREC), index.format(0))
+            }
+            Self::Slice {
+                expr, start, end, ..
+            } => format!(
+                "{}[{}:{}]",
+                expr.format(Self::INDEX_PREC),
+                start.as_ref().map_or(String::new(), |e| e.format(0)),
+                end.as_ref().map_or(String::new(), |e| e.format(0)),
+            ),
+            Self::Ident(s, _) => s.to_string(),
+            Self::Literal(y, _) => y.to_string(),
+            Self::Array(elems, _) => format!("[{}]", format_args(elems)),
+        }
+    }
+}
+
+/// Formats a comma-separated list of Yolk expressions.
+fn format_args(args: &[YolkExpr]) -> String {
+    let args: Vec<String> = args.iter().map(|a| a.format(0)).collect();
+    args.join(", ")
+}
+
+/// Wraps a formatted expression in parentheses if needed.
+fn wrap(s: String, wrapped: bool) -> String {
+    if wrapped {
+        format!("({})", s)
+    } else {
+        s
+    }
+}
+
+/// Represents a region of Yolk source code.
+///
+/// Spans are ignored when comparing nodes, so ASTs with the same structure
+/// are equal regardless of where they were parsed from.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Span {
+    /// The byte offset of the start of the span.
+    pub start: usize,
+    /// The byte offset of the end of the span.
+    pub end: usize,
+    /// The line of the start of the span, starting from 1.
+    pub line: usize,
+    /// The column of the start of the span, starting from 1.
+    pub col: usize,
+    /// The index of the source file that contains the span.
+    ///
+    /// The main file has index 0, and files loaded by `use` statements
+    /// have increasing indices.
+    pub file: usize,
+}
+
+impl Span {
+    /// Creates a span that starts at one span and ends at another.
+    pub fn join(&self, other: &Span) -> Span {
+        Span {
+            start: self.start,
+            end: other.end,
+            line: self.line,
+            col: self.col,
+            file: self.file,
+        }
+    }
+}
+
+impl PartialEq for Span {
+    fn eq(&self, _: &Span) -> bool {
+        true
+    }
+}
+
+impl fmt::Display for Span {
+    /// Formats a span as a line and column.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.col)
+    }
 }
 
 /// Represents a Yolol program.
@@ -97,17 +390,46 @@ impl YololProgram {
     pub fn optimize(self) -> Self {
         optimize(self)
     }
+
+    /// Optimizes a Yolol program, keeping the final values of the given roots.
+    pub fn optimize_with_roots(self, roots: &HashSet<String>) -> Self {
+        optimize_with_roots(self, roots)
+    }
+
+    /// Renames the variables in a Yolol program, except for the given names.
+    pub fn minify(self, keep: &HashSet<String>) -> (Self, NameMap) {
+        minify(self, keep)
+    }
+
+    /// Lays out a Yolol program on the lines of a chip.
+    pub fn layout(&self, limits: &ChipLimits) -> Result<ChipLayout, LayoutError> {
+        layout(&self.stmts, limits)
+    }
+
+    /// Splits a Yolol program across as few chips as possible.
+    pub fn partition(&self, limits: &ChipLimits) -> Result<Partition, LayoutError> {
+        partition(&self.stmts, limits)
+    }
+}
+
+impl FromStr for YololProgram {
+    type Err = Vec<YolkError>;
+
+    /// Parses a Yolol program from a string.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_yolol(s)
+    }
 }
 
 impl From<Vec<YololStmt>> for YololProgram {
     /// Converts Yolol statements to a program.
     fn from(stmts: Vec<YololStmt>) -> Self {
-        YololProgram { stmts: stmts }
+        YololProgram { stmts }
     }
 }
 
 impl TryFrom<YolkProgram> for YololProgram {
-    type Error = YolkError;
+    type Error = Vec<YolkError>;
 
     /// Converts a Yolk program into a Yolol program.
     fn try_from(program: YolkProgram) -> Result<Self, Self::Error> {
@@ -117,26 +439,11 @@ impl TryFrom<YolkProgram> for YololProgram {
 
 impl fmt::Display for YololProgram {
     /// Formats a Yolol program as a string.
+    ///
+    /// Statements are packed in order into lines that fit on a chip, but the
+    /// number of lines is not limited.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let mut buffer = String::new();
-        let mut line = String::new();
-        // Iterate directly over stmts to avoid taking ownership
-        for stmt in self.stmts.iter() {
-            match stmt {
-                YololStmt::Assign { ident, expr } => {
-                    let stmt = format!(" {}={}", ident, expr.to_string());
-                    if line.len() + stmt.len() >= 70 {
-                        buffer.push_str(&format!("{}\n", line.trim()));
-                        line.clear();
-                    }
-                    line.push_str(&stmt);
-                }
-            }
-        }
-        if line.len() > 0 {
-            buffer.push_str(&format!("{}\n", line.trim()));
-        }
-        write!(f, "{}", buffer.trim().to_string())
+        write!(f, "{}", pack(&self.stmts, ChipLimits::default().width))
     }
 }
 
@@ -194,7 +501,12 @@ impl YololExpr {
         match self {
             Self::Prefix { op, expr } => {
                 let prec = op.to_precedence();
-                let (expr, child_wrapped) = expr.format(prec);
+                let (mut expr, mut child_wrapped) = expr.format(prec);
+                // Consecutive minus signs would be parsed as a decrement
+                if *op == PrefixOp::Neg && expr.starts_with('-') {
+                    expr = format!("({})", expr);
+                    child_wrapped = true;
+                }
                 let wrapped = prec < parent_prec;
                 // Alphabetic ops must be surrounded with whitespace or parentheses
                 let is_alpha = op.to_string().chars().all(char::is_alphabetic);
@@ -214,7 +526,15 @@ impl YololExpr {
             Self::Infix { lhs, op, rhs } => {
                 let prec = op.to_precedence();
                 let (lhs, lhs_wrapped) = lhs.format(prec);
-                let (rhs, rhs_wrapped) = rhs.format(prec);
+                // If the op is not associative, we cannot reduce "a-(b+c)" to "a-b+c"
+                // Multiplication truncates, so we cannot reduce "a*(b*c)" to "a*b*c" either
+                let rhs_prec = if *op == InfixOp::Add { prec } else { prec + 1 };
+                let (mut rhs, mut rhs_wrapped) = rhs.format(rhs_prec);
+                // Consecutive minus signs would be parsed as a decrement
+                if *op == InfixOp::Sub && rhs.starts_with('-') {
+                    rhs = format!("({})", rhs);
+                    rhs_wrapped = true;
+                }
                 // If the op is associative, we can reduce "(a+b)+c" to "a+b+c"
                 let wrapped = if op.is_associative() {
                     prec < parent_prec
@@ -246,7 +566,7 @@ impl YololExpr {
 }
 
 /// Represents a prefix operation.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PrefixOp {
     Neg,
     Not,
@@ -261,7 +581,23 @@ pub enum PrefixOp {
 }
 
 impl PrefixOp {
-    fn to_precedence(&self) -> u32 {
+    /// Returns the Yolol symbol of the operation.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Neg => "-",
+            Self::Not => "not",
+            Self::Abs => "abs",
+            Self::Sqrt => "sqrt",
+            Self::Sin => "sin",
+            Self::Cos => "cos",
+            Self::Tan => "tan",
+            Self::Asin => "asin",
+            Self::Acos => "acos",
+            Self::Atan => "atan",
+        }
+    }
+
+    pub(crate) fn to_precedence(&self) -> u32 {
         match self {
             Self::Neg => 100,
             _ => 90,
@@ -271,24 +607,68 @@ impl PrefixOp {
 
 impl fmt::Display for PrefixOp {
     /// Formats a prefix operation as a string.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Represents a built-in operation that folds any number of arguments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldOp {
+    Sum,
+    Product,
+    Min,
+    Max,
+}
+
+impl fmt::Display for FoldOp {
+    /// Formats a fold operation as a string.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
-            Self::Neg => write!(f, "-"),
-            Self::Not => write!(f, "not"),
-            Self::Abs => write!(f, "abs"),
-            Self::Sqrt => write!(f, "sqrt"),
-            Self::Sin => write!(f, "sin"),
-            Self::Cos => write!(f, "cos"),
-            Self::Tan => write!(f, "tan"),
-            Self::Asin => write!(f, "asin"),
-            Self::Acos => write!(f, "acos"),
-            Self::Atan => write!(f, "atan"),
+            Self::Sum => write!(f, "sum"),
+            Self::Product => write!(f, "product"),
+            Self::Min => write!(f, "min"),
+            Self::Max => write!(f, "max"),
         }
     }
 }
 
-/// Represents an infix operation.
+/// Represents a built-in operation with a fixed number of arguments.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuiltinOp {
+    Select,
+    Clamp,
+    Len,
+    Range,
+    Fill,
+}
+
+impl BuiltinOp {
+    /// Returns the number of arguments that a built-in operation takes.
+    pub fn arity(&self) -> usize {
+        match self {
+            Self::Select | Self::Clamp => 3,
+            Self::Range | Self::Fill => 2,
+            Self::Len => 1,
+        }
+    }
+}
+
+impl fmt::Display for BuiltinOp {
+    /// Formats a built-in operation as a string.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Select => write!(f, "select"),
+            Self::Clamp => write!(f, "clamp"),
+            Self::Len => write!(f, "len"),
+            Self::Range => write!(f, "range"),
+            Self::Fill => write!(f, "fill"),
+        }
+    }
+}
+
+/// Represents an infix operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum InfixOp {
     Add,
     Sub,
@@ -307,7 +687,27 @@ pub enum InfixOp {
 }
 
 impl InfixOp {
-    fn to_precedence(&self) -> u32 {
+    /// Returns the Yolol symbol of the operation.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Add => "+",
+            Self::Sub => "-",
+            Self::Mul => "*",
+            Self::Div => "/",
+            Self::Mod => "%",
+            Self::Exp => "^",
+            Self::LessThan => "<",
+            Self::LessEqual => "<=",
+            Self::GreaterThan => ">",
+            Self::GreaterEqual => ">=",
+            Self::Equal => "==",
+            Self::NotEqual => "!=",
+            Self::And => "and",
+            Self::Or => "or",
+        }
+    }
+
+    pub(crate) fn to_precedence(&self) -> u32 {
         match self {
             Self::Exp => 80,
             Self::Mul | Self::Div | Self::Mod => 70,
@@ -319,6 +719,15 @@ impl InfixOp {
         }
     }
 
+    fn to_yolk_precedence(&self) -> u32 {
+        // Unlike Yolol, `and` binds tighter than `or` in Yolk
+        match self {
+            Self::And => 30,
+            Self::Or => 20,
+            _ => self.to_precedence(),
+        }
+    }
+
     /// Returns whether or not an infix operation is associative.
     pub fn is_associative(&self) -> bool {
         match self {
@@ -331,21 +740,6 @@ impl InfixOp {
 impl fmt::Display for InfixOp {
     /// Formats an infix operation as a string.
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Self::Add => write!(f, "+"),
-            Self::Sub => write!(f, "-"),
-            Self::Mul => write!(f, "*"),
-            Self::Div => write!(f, "/"),
-            Self::Mod => write!(f, "%"),
-            Self::Exp => write!(f, "^"),
-            Self::LessThan => write!(f, "<"),
-            Self::LessEqual => write!(f, "<="),
-            Self::GreaterThan => write!(f, ">"),
-            Self::GreaterEqual => write!(f, ">="),
-            Self::Equal => write!(f, "=="),
-            Self::NotEqual => write!(f, "!="),
-            Self::And => write!(f, "and"),
-            Self::Or => write!(f, "or"),
-        }
+        write!(f, "{}", self.as_str())
     }
 }
@@ -1,7 +1,9 @@
 use num_traits::identities::Zero;
 use yolol_number::YololNumber;
 
-use crate::ast::{InfixOp, PrefixOp, YololExpr, YololProgram, YololStmt};
+use crate::ast::{
+    InfixOp, PrefixOp, Span, YolkExpr, YolkProgram, YolkStmt, YololExpr, YololProgram, YololStmt,
+};
 use crate::error::YolkError;
 
 #[test]
@@ -96,7 +98,7 @@ fn test_format_neg_neg() -> Result<(), YolkError> {
         }),
     }]
     .into();
-    assert_eq!(yolol.to_string(), "a=--b");
+    assert_eq!(yolol.to_string(), "a=-(-b)");
     Ok(())
 }
 
@@ -115,6 +117,43 @@ fn test_format_add() -> Result<(), YolkError> {
     Ok(())
 }
 
+#[test]
+fn test_format_sub_neg() -> Result<(), YolkError> {
+    let yolol: YololProgram = vec![YololStmt::Assign {
+        ident: "a".to_string(),
+        expr: Box::new(YololExpr::Infix {
+            lhs: Box::new(YololExpr::Ident("b".to_string())),
+            op: InfixOp::Sub,
+            rhs: Box::new(YololExpr::Prefix {
+                op: PrefixOp::Neg,
+                expr: Box::new(YololExpr::Ident("c".to_string())),
+            }),
+        }),
+    }]
+    .into();
+    assert_eq!(yolol.to_string(), "a=b-(-c)");
+    Ok(())
+}
+
+#[test]
+fn test_format_sub_add() -> Result<(), YolkError> {
+    let yolol: YololProgram = vec![YololStmt::Assign {
+        ident: "a".to_string(),
+        expr: Box::new(YololExpr::Infix {
+            lhs: Box::new(YololExpr::Ident("b".to_string())),
+            op: InfixOp::Sub,
+            rhs: Box::new(YololExpr::Infix {
+                lhs: Box::new(YololExpr::Ident("c".to_string())),
+                op: InfixOp::Add,
+                rhs: Box::new(YololExpr::Ident("d".to_string())),
+            }),
+        }),
+    }]
+    .into();
+    assert_eq!(yolol.to_string(), "a=b-(c+d)");
+    Ok(())
+}
+
 #[test]
 fn test_format_div() -> Result<(), YolkError> {
     let yolol: YololProgram = vec![YololStmt::Assign {
@@ -206,6 +245,25 @@ fn test_format_div_div() -> Result<(), YolkError> {
     Ok(())
 }
 
+#[test]
+fn test_format_mul_mul() -> Result<(), YolkError> {
+    let yolol: YololProgram = vec![YololStmt::Assign {
+        ident: "a".to_string(),
+        expr: Box::new(YololExpr::Infix {
+            lhs: Box::new(YololExpr::Ident("b".to_string())),
+            op: InfixOp::Mul,
+            rhs: Box::new(YololExpr::Infix {
+                lhs: Box::new(YololExpr::Ident("c".to_string())),
+                op: InfixOp::Mul,
+                rhs: Box::new(YololExpr::Ident("d".to_string())),
+            }),
+        }),
+    }]
+    .into();
+    assert_eq!(yolol.to_string(), "a=b*(c*d)");
+    Ok(())
+}
+
 #[test]
 fn test_format_literal() -> Result<(), YolkError> {
     let yolol: YololProgram = vec![YololStmt::Assign {
@@ -227,3 +285,107 @@ fn test_format_ident() -> Result<(), YolkError> {
     assert_eq!(yolol.to_string(), "a=b");
     Ok(())
 }
+
+/// Checks that a Yolk program is formatted as expected and parses back to the same tree.
+fn assert_yolk_format(source: &str, expected: &str) -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = source.parse()?;
+    assert_eq!(yolk.to_string(), expected);
+    let parsed: YolkProgram = yolk.to_string().parse()?;
+    assert_eq!(parsed, yolk);
+    Ok(())
+}
+
+#[test]
+fn test_format_yolk_stmts() -> Result<(), Vec<YolkError>> {
+    let source = "use \"lib/vector.yolk\"\nimport a\ndefine f(A,B)=A+B\nlet b=f(a,1)\nset b=b+1\nexport b\nexport a as c";
+    let expected = "use \"lib/vector.yolk\"\nimport a\ndefine f(A, B) = A + B\nlet b = f(a, 1)\nset b = b + 1\nexport b\nexport a as c";
+    assert_yolk_format(source, expected)
+}
+
+#[test]
+fn test_format_yolk_comments() -> Result<(), Vec<YolkError>> {
+    assert_yolk_format("// comment\n\nlet a = 1 // comment\n", "let a = 1")
+}
+
+#[test]
+fn test_format_yolk_arithmetic() -> Result<(), Vec<YolkError>> {
+    assert_yolk_format("let a = b+c*d", "let a = b + c * d")?;
+    assert_yolk_format("let a = (b+c)*d", "let a = (b + c) * d")?;
+    assert_yolk_format("let a = (b-c)-d", "let a = b - c - d")?;
+    assert_yolk_format("let a = b-(c-d)", "let a = b - (c - d)")?;
+    assert_yolk_format("let a = b+(c+d)", "let a = b + (c + d)")?;
+    assert_yolk_format("let a = b^c^d", "let a = b ^ c ^ d")?;
+    assert_yolk_format("let a = (b^c)^d", "let a = (b ^ c) ^ d")
+}
+
+#[test]
+fn test_format_yolk_logical() -> Result<(), Vec<YolkError>> {
+    assert_yolk_format("let a = b or c and d", "let a = b or c and d")?;
+    assert_yolk_format("let a = (b or c) and d", "let a = (b or c) and d")?;
+    assert_yolk_format("let a = b == (c < d)", "let a = b == c < d")?;
+    assert_yolk_format("let a = (b == c) < d", "let a = (b == c) < d")?;
+    assert_yolk_format("let a = not(b and c)", "let a = not(b and c)")
+}
+
+#[test]
+fn test_format_yolk_neg() -> Result<(), Vec<YolkError>> {
+    assert_yolk_format("let a = -b^2", "let a = -b ^ 2")?;
+    assert_yolk_format("let a = -(b^2)", "let a = -(b ^ 2)")?;
+    assert_yolk_format("let a = --b", "let a = --b")?;
+    assert_yolk_format("let a = -b[0]", "let a = -b[0]")?;
+    assert_yolk_format("let a = (-b)[0]", "let a = (-b)[0]")?;
+    assert_yolk_format("let a = b - -1", "let a = b - -1")?;
+    assert_yolk_format("let a = -(1)", "let a = -(1)")?;
+    assert_yolk_format("let a = -(-1)", "let a = -(-1)")?;
+    assert_yolk_format("let a = --(1)", "let a = --1")?;
+    assert_yolk_format("let a = --(-1)", "let a = --(-1)")?;
+    assert_yolk_format("let a = -abs(b)", "let a = -abs(b)")
+}
+
+#[test]
+fn test_format_yolk_calls() -> Result<(), Vec<YolkError>> {
+    assert_yolk_format("let a = sum(b,c)", "let a = sum(b, c)")?;
+    assert_yolk_format("let a = clamp(b,0,1)", "let a = clamp(b, 0, 1)")?;
+    assert_yolk_format("let a = vector::dot(b,\n  c)", "let a = vector::dot(b, c)")
+}
+
+#[test]
+fn test_format_yolk_arrays() -> Result<(), Vec<YolkError>> {
+    assert_yolk_format("let a = [1,2.5,-3]", "let a = [1, 2.5, -3]")?;
+    assert_yolk_format("let a = [1,2][b+1]", "let a = [1, 2][b + 1]")?;
+    assert_yolk_format("let a = (b+c)[0]", "let a = (b + c)[0]")?;
+    assert_yolk_format("let a = b[1:]", "let a = b[1:]")?;
+    assert_yolk_format("let a = b[:2]", "let a = b[:2]")?;
+    assert_yolk_format("let a = b[:]", "let a = b[:]")
+}
+
+#[test]
+fn test_format_yolk_neg_literal() -> Result<(), Vec<YolkError>> {
+    let span = Span::default();
+    let neg = |expr| YolkExpr::Prefix {
+        op: PrefixOp::Neg,
+        expr: Box::new(expr),
+        span,
+    };
+    let yolk: YolkProgram = vec![
+        YolkStmt::Let {
+            ident: "a".to_string(),
+            expr: Box::new(neg(YolkExpr::Literal(YololNumber::from_value(1), span))),
+            span,
+        },
+        YolkStmt::Let {
+            ident: "b".to_string(),
+            expr: Box::new(neg(YolkExpr::Index {
+                expr: Box::new(YolkExpr::Literal(YololNumber::from_value(-1), span)),
+                index: Box::new(YolkExpr::Ident("a".to_string(), span)),
+                span,
+            })),
+            span,
+        },
+    ]
+    .into();
+    assert_eq!(yolk.to_string(), "let a = -(1)\nlet b = -(-1[a])");
+    let parsed: YolkProgram = yolk.to_string().parse()?;
+    assert_eq!(parsed, yolk);
+    Ok(())
+}
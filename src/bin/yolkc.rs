@@ -1,12 +1,20 @@
 #[macro_use]
 extern crate clap;
 
-use clap::{App, Arg};
+use clap::{App, Arg, ArgMatches, SubCommand};
 
+use yolk::diagnostic::Diagnostic;
+use yolk::error::{LayoutError, YolkError};
+use yolk::formatter::format;
+use yolk::layout::ChipLimits;
+use yolk::loader::{Loader, SourceFile};
+use yolk::minifier::external_names;
+use yolk::transpiler::{exported_names, transpile_with_options, Options};
 use yolk::{YolkProgram, YololProgram};
 
-use std::convert::TryInto;
 use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
 
 fn main() {
     let matches = App::new("yolkc")
@@ -20,29 +28,189 @@ fn main() {
                 .short("i")
                 .long("infile"),
         )
+        .arg(
+            Arg::with_name("no-prelude")
+                .help("do not define the prelude functions")
+                .long("no-prelude"),
+        )
+        .arg(
+            Arg::with_name("root")
+                .help("keep the final value of a variable, dropping all others")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .short("r")
+                .long("root"),
+        )
+        .arg(
+            Arg::with_name("minify")
+                .help("rename variables that are not imported or exported")
+                .long("minify"),
+        )
+        .arg(
+            Arg::with_name("chip")
+                .help("lay out the output on a chip, failing if it does not fit")
+                .takes_value(true)
+                .possible_values(&["basic", "advanced", "professional"])
+                .long("chip"),
+        )
+        .arg(
+            Arg::with_name("partition")
+                .help("split the output across as many chips as needed")
+                .requires("chip")
+                .long("partition"),
+        )
         .arg(
             Arg::with_name("debug")
                 .help("print debug messages")
                 .long("debug"),
         )
+        .subcommand(
+            SubCommand::with_name("fmt")
+                .about("Formats Yolk files in place")
+                .arg(
+                    Arg::with_name("check")
+                        .help("exit with an error if a file is not formatted, instead of formatting it")
+                        .long("check"),
+                )
+                .arg(
+                    Arg::with_name("files")
+                        .help("files to format")
+                        .required(true)
+                        .multiple(true),
+                ),
+        )
         .get_matches();
 
+    if let Some(matches) = matches.subcommand_matches("fmt") {
+        process::exit(format_files(matches));
+    }
+
     let debug = matches.is_present("debug");
+    let options = Options {
+        prelude: !matches.is_present("no-prelude"),
+    };
 
     if let Some(infile) = matches.value_of("infile") {
         let source = fs::read_to_string(infile).expect("cannot read from file");
-        let yolk: YolkProgram = source.parse().unwrap_or_else(|e| panic!("{}", e));
+        let mut loader = Loader::new();
+        let loaded = loader.load(Path::new(infile), &source);
+        let files = loader.files();
+        let report = |errors: Vec<YolkError>| -> ! {
+            for e in errors.iter() {
+                eprintln!("{}", render(&Diagnostic::from(e), files));
+            }
+            eprintln!("error: aborting due to {} error(s)", errors.len());
+            process::exit(1)
+        };
+        let yolk: YolkProgram = loaded.unwrap_or_else(|e| report(e));
         if debug {
             eprintln!("{:?}\n", yolk);
         }
-        let yolol: YololProgram = yolk.try_into().unwrap_or_else(|e| panic!("{}", e));
+        let mut keep = external_names(&yolk);
+        // Programs that export variables only need the final values of the exported names
+        let mut roots = exported_names(&yolk);
+        let (result, warnings) = transpile_with_options(yolk, &options);
+        for w in warnings.iter() {
+            eprintln!("{}", render(&Diagnostic::from(w), files));
+        }
+        let yolol: YololProgram = result.unwrap_or_else(|e| report(e));
         if debug {
             eprintln!("{:?}\n", yolol);
         }
-        let optimized = yolol.optimize();
+        roots.extend(
+            matches
+                .values_of("root")
+                .into_iter()
+                .flatten()
+                .map(String::from),
+        );
+        keep.extend(roots.iter().cloned());
+        let optimized = if roots.is_empty() {
+            yolol.optimize()
+        } else {
+            yolol.optimize_with_roots(&roots)
+        };
         if debug {
             eprintln!("{:?}\n", optimized);
         }
-        println!("{}", optimized.to_string());
+        let output = if matches.is_present("minify") {
+            let (minified, names) = optimized.minify(&keep);
+            if debug {
+                eprintln!("{}\n", names);
+            }
+            minified
+        } else {
+            optimized
+        };
+        match matches.value_of("chip") {
+            Some(chip) => {
+                let limits = match chip {
+                    "basic" => ChipLimits::BASIC,
+                    "advanced" => ChipLimits::ADVANCED,
+                    _ => ChipLimits::PROFESSIONAL,
+                };
+                let fail = |e: LayoutError| -> ! {
+                    eprintln!("error: {}", e);
+                    process::exit(1)
+                };
+                if matches.is_present("partition") {
+                    let partition = output.partition(&limits).unwrap_or_else(|e| fail(e));
+                    if debug {
+                        eprintln!("{}\n", partition.fields());
+                    }
+                    println!("{}", partition.to_string());
+                } else {
+                    let layout = output.layout(&limits).unwrap_or_else(|e| fail(e));
+                    println!("{}", layout.to_string());
+                }
+            }
+            None => println!("{}", output.to_string()),
+        }
+    }
+}
+
+/// Formats files in place, or checks that they are already formatted.
+///
+/// Returns the exit code.
+fn format_files(matches: &ArgMatches) -> i32 {
+    let check = matches.is_present("check");
+    let mut code = 0;
+    for path in matches.values_of("files").into_iter().flatten() {
+        let source = fs::read_to_string(path).expect("cannot read from file");
+        match format(&source) {
+            Ok(formatted) if formatted == source => (),
+            Ok(_) if check => {
+                println!("{} is not formatted", path);
+                code = 1;
+            }
+            Ok(formatted) => fs::write(path, formatted).expect("cannot write to file"),
+            Err(errors) => {
+                // Files are formatted on their own, so every span refers to this file
+                let files = [SourceFile {
+                    path: PathBuf::from(path),
+                    source,
+                }];
+                for e in errors.iter() {
+                    eprintln!("{}", render(&Diagnostic::from(e), &files));
+                }
+                eprintln!("error: aborting due to {} error(s)", errors.len());
+                code = 1;
+            }
+        }
+    }
+    code
+}
+
+/// Renders a diagnostic with snippets from the file that it refers to.
+///
+/// If the file is unknown, only the message of the diagnostic is rendered.
+fn render(diagnostic: &Diagnostic, files: &[SourceFile]) -> String {
+    match files.get(diagnostic.primary.span.file) {
+        Some(file) => diagnostic.render(&file.source, &file.path.to_string_lossy()),
+        None => format!(
+            "{}[{}]: {}",
+            diagnostic.level, diagnostic.code, diagnostic.msg
+        ),
     }
 }
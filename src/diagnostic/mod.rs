@@ -0,0 +1,362 @@
+use crate::ast::Span;
+use crate::error::YolkError;
+use crate::warning::YolkWarning;
+
+use std::fmt;
+
+#[cfg(test)]
+mod tests;
+
+/// Represents a message attached to a span of source code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Label {
+    pub span: Span,
+    pub msg: String,
+}
+
+impl Label {
+    /// Creates a new label.
+    pub fn new(span: &Span, msg: &str) -> Label {
+        Label {
+            span: *span,
+            msg: msg.to_string(),
+        }
+    }
+}
+
+/// Represents the severity of a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Level {
+    /// Formats a level as a string.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+/// Represents a diagnostic that can be rendered with source snippets.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    /// The severity of the diagnostic.
+    pub level: Level,
+    /// The stable code of the diagnostic (e.g. `Y0001`).
+    pub code: String,
+    /// The headline of the diagnostic.
+    pub msg: String,
+    /// Points at the code that caused the diagnostic.
+    pub primary: Label,
+    /// Points at related code (e.g. a previous definition).
+    pub secondary: Vec<Label>,
+    /// Provides additional context that is not tied to a span.
+    pub notes: Vec<String>,
+}
+
+impl From<&YolkError> for Diagnostic {
+    /// Converts an error to a diagnostic.
+    fn from(error: &YolkError) -> Self {
+        let span = error.span();
+        let mut secondary = Vec::new();
+        let mut notes = Vec::new();
+        let (msg, primary) = match error {
+            YolkError::InvalidSyntax { msg, .. } => ("invalid syntax".to_string(), msg.to_string()),
+            YolkError::ImportExisting { var, prev, .. } => {
+                secondary.push(Label::new(prev, "first defined here"));
+                (
+                    format!("cannot import existing variable `{}`", var),
+                    "imported again here".to_string(),
+                )
+            }
+            YolkError::ImportKeyword { var, .. } => (
+                format!("cannot import keyword `{}`", var),
+                "reserved keyword".to_string(),
+            ),
+            YolkError::DefineExisting { func, prev, .. } => {
+                secondary.push(Label::new(prev, "first defined here"));
+                (
+                    format!("cannot define existing function `{}`", func),
+                    "defined again here".to_string(),
+                )
+            }
+            YolkError::DefineKeyword { func, .. } => (
+                format!("cannot define keyword `{}`", func),
+                "reserved keyword".to_string(),
+            ),
+            YolkError::AssignExisting { var, prev, .. } => {
+                secondary.push(Label::new(prev, "first defined here"));
+                (
+                    format!("cannot assign to existing variable `{}`", var),
+                    "assigned again here".to_string(),
+                )
+            }
+            YolkError::AssignKeyword { var, .. } => (
+                format!("cannot assign to keyword `{}`", var),
+                "reserved keyword".to_string(),
+            ),
+            YolkError::SetImport { var, prev, .. } => {
+                secondary.push(Label::new(prev, "imported here"));
+                notes.push("imported variables cannot be changed".to_string());
+                (
+                    format!("cannot set imported variable `{}`", var),
+                    "set here".to_string(),
+                )
+            }
+            YolkError::ExportExisting { name, prev, .. } => {
+                secondary.push(Label::new(prev, "first used here"));
+                notes.push("Yolol identifiers are case-insensitive".to_string());
+                (
+                    format!("cannot export to existing name `{}`", name),
+                    "exported again here".to_string(),
+                )
+            }
+            YolkError::AssignConflict {
+                var, other, prev, ..
+            } => {
+                secondary.push(Label::new(prev, &format!("`{}` defined here", other)));
+                notes.push("Yolol identifiers are case-insensitive".to_string());
+                (
+                    format!(
+                        "variable `{}` conflicts with existing variable `{}`",
+                        var, other
+                    ),
+                    "conflicting name".to_string(),
+                )
+            }
+            YolkError::UndefinedFunction { func, .. } => (
+                format!("undefined function `{}`", func),
+                "not defined".to_string(),
+            ),
+            YolkError::UndefinedVariable { var, .. } => (
+                format!("undefined variable `{}`", var),
+                "not defined".to_string(),
+            ),
+            YolkError::DuplicateParams { func, .. } => (
+                format!("duplicate parameters in function `{}`", func),
+                "parameter names must be unique".to_string(),
+            ),
+            YolkError::RecursiveCall { func, .. } => (
+                format!("recursive call in function `{}`", func),
+                "functions must not call themselves".to_string(),
+            ),
+            YolkError::WrongNumberOfArgs {
+                func,
+                expected,
+                found,
+                def,
+                ..
+            } => {
+                // Built-in functions have no definition to point to
+                if let Some(def) = def {
+                    secondary.push(Label::new(def, "function defined here"));
+                }
+                (
+                    format!(
+                        "function `{}` takes {} argument(s), but {} were provided",
+                        func, expected, found
+                    ),
+                    format!("expected {} argument(s)", expected),
+                )
+            }
+            YolkError::MismatchedArrays {
+                lhs_len, rhs_len, ..
+            } => {
+                notes.push("arrays must have the same length to be combined".to_string());
+                (
+                    "mismatched array lengths".to_string(),
+                    format!("arrays of length {} and {}", lhs_len, rhs_len),
+                )
+            }
+            YolkError::NestedArrays { .. } => {
+                notes.push("arrays may only contain numbers".to_string());
+                (
+                    "cannot nest arrays".to_string(),
+                    "this element is an array".to_string(),
+                )
+            }
+            YolkError::MalformedAst { msg, .. } => {
+                notes.push("the AST was not produced by the parser".to_string());
+                ("malformed AST".to_string(), msg.to_string())
+            }
+            YolkError::IndexOutOfRange { index, len, .. } => {
+                notes.push("indices start at 0".to_string());
+                (
+                    "index out of range".to_string(),
+                    format!(
+                        "index {} is out of range for array of length {}",
+                        index, len
+                    ),
+                )
+            }
+            YolkError::NonConstantIndex { .. } => {
+                notes.push("slices must have a length that is known at compile time".to_string());
+                (
+                    "slice bound is not a constant".to_string(),
+                    "cannot be evaluated at compile time".to_string(),
+                )
+            }
+            YolkError::InvalidIndex { msg, .. } => ("invalid index".to_string(), msg.to_string()),
+            YolkError::UseFailed { path, msg, .. } => {
+                (format!("cannot use `{}`", path), msg.to_string())
+            }
+            YolkError::UseCycle { path, .. } => {
+                notes.push("a file must not use itself, directly or indirectly".to_string());
+                (
+                    format!("cyclic use of `{}`", path),
+                    "used again here".to_string(),
+                )
+            }
+            YolkError::LibraryStmt { .. } => {
+                notes.push("only `define` and `use` statements can be shared".to_string());
+                (
+                    "used files may only contain definitions".to_string(),
+                    "not a definition".to_string(),
+                )
+            }
+            YolkError::NamespaceConflict {
+                path,
+                namespace,
+                prev,
+                ..
+            } => {
+                secondary.push(Label::new(prev, "namespace first used here"));
+                notes.push("functions are namespaced by the name of their file".to_string());
+                (
+                    format!("namespace `{}` is already used", namespace),
+                    format!("`{}` has the same name as another used file", path),
+                )
+            }
+            YolkError::InvalidArgument { func, msg, .. } => {
+                (format!("invalid argument to `{}`", func), msg.to_string())
+            }
+            YolkError::NonConstantArgument { func, .. } => {
+                notes.push("array lengths must be known at compile time".to_string());
+                (
+                    format!("argument to `{}` is not a constant", func),
+                    "cannot be evaluated at compile time".to_string(),
+                )
+            }
+            YolkError::LargeExtremum { func, len, max, .. } => {
+                notes.push("each number is compared to every other number at runtime".to_string());
+                notes.push(format!(
+                    "at most {} numbers may be compared at runtime, but constants are compared at compile time",
+                    max
+                ));
+                (
+                    format!("`{}` compares too many numbers", func),
+                    format!("compares {} numbers at runtime", len),
+                )
+            }
+        };
+        Diagnostic {
+            level: Level::Error,
+            code: error.code().to_string(),
+            msg,
+            primary: Label::new(&span, &primary),
+            secondary,
+            notes,
+        }
+    }
+}
+
+impl From<&YolkWarning> for Diagnostic {
+    /// Converts a warning to a diagnostic.
+    fn from(warning: &YolkWarning) -> Self {
+        let (msg, primary, notes) = match warning {
+            YolkWarning::DynamicIndex { len, chars, .. } => (
+                "index is not a constant".to_string(),
+                format!("expands to {} term(s) and {} character(s)", len, chars),
+                vec![
+                    "the index is compared to every position of the array at runtime".to_string(),
+                    "an index that is out of range evaluates to 0".to_string(),
+                ],
+            ),
+        };
+        Diagnostic {
+            level: Level::Warning,
+            code: warning.code().to_string(),
+            msg,
+            primary: Label::new(&warning.span(), &primary),
+            secondary: Vec::new(),
+            notes,
+        }
+    }
+}
+
+impl Diagnostic {
+    /// Renders a diagnostic with snippets from the source code it refers to.
+    ///
+    /// Lines are rendered in order, with `^` underlining the primary label and
+    /// `-` underlining secondary labels.
+    pub fn render(&self, source: &str, filename: &str) -> String {
+        let mut labels: Vec<(&Label, bool)> = vec![(&self.primary, true)];
+        labels.extend(self.secondary.iter().map(|label| (label, false)));
+        // Labels without a location, or in other files, cannot be rendered as snippets
+        labels
+            .retain(|(label, _)| label.span.line > 0 && label.span.file == self.primary.span.file);
+        labels.sort_by_key(|(label, _)| (label.span.line, label.span.col));
+
+        let width = labels
+            .iter()
+            .map(|(label, _)| label.span.line.to_string().len())
+            .max()
+            .unwrap_or(1);
+        let gutter = " ".repeat(width);
+
+        let mut buffer = format!("{}[{}]: {}\n", self.level, self.code, self.msg);
+        if self.primary.span.line > 0 {
+            buffer.push_str(&format!(
+                "{}--> {}:{}\n",
+                gutter, filename, self.primary.span
+            ));
+        } else {
+            buffer.push_str(&format!("{}--> {}\n", gutter, filename));
+        }
+        if !labels.is_empty() {
+            buffer.push_str(&format!("{} |\n", gutter));
+        }
+        let mut prev_line = None;
+        for (label, is_primary) in labels.iter() {
+            let line = label.span.line;
+            if prev_line != Some(line) {
+                if let Some(prev) = prev_line {
+                    if line > prev + 1 {
+                        buffer.push_str("...\n");
+                    }
+                }
+                let text = source.lines().nth(line - 1).unwrap_or("");
+                buffer.push_str(&format!("{:>width$} | {}\n", line, text, width = width));
+            }
+            let marker = if *is_primary { "^" } else { "-" };
+            buffer.push_str(&format!(
+                "{} | {}{} {}\n",
+                gutter,
+                " ".repeat(label.span.col.saturating_sub(1)),
+                marker.repeat(underline_len(source, &label.span)),
+                label.msg
+            ));
+            prev_line = Some(line);
+        }
+        if !self.notes.is_empty() {
+            buffer.push_str(&format!("{} |\n", gutter));
+        }
+        for note in self.notes.iter() {
+            buffer.push_str(&format!("{} = note: {}\n", gutter, note));
+        }
+        buffer
+    }
+}
+
+/// Counts the characters of a span on its first line.
+fn underline_len(source: &str, span: &Span) -> usize {
+    source
+        .get(span.start..span.end)
+        .map(|s| s.chars().take_while(|c| *c != '\n').count())
+        .unwrap_or(0)
+        .max(1)
+}
@@ -0,0 +1,156 @@
+use crate::ast::{YolkProgram, YololProgram};
+use crate::diagnostic::Diagnostic;
+use crate::error::YolkError;
+use crate::transpiler::transpile_with_warnings;
+
+use std::convert::TryFrom;
+
+fn render(source: &str) -> String {
+    let errors = match source.parse::<YolkProgram>() {
+        Ok(yolk) => YololProgram::try_from(yolk).expect_err("expected an error"),
+        Err(errors) => errors,
+    };
+    Diagnostic::from(&errors[0]).render(source, "test.yolk")
+}
+
+#[test]
+fn test_render_undefined_variable() {
+    let rendered = render("let a = 1\nlet b = a + c");
+    let expected = "\
+error[Y0010]: undefined variable `c`
+ --> test.yolk:2:13
+  |
+2 | let b = a + c
+  |             ^ not defined
+";
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn test_render_assign_conflict() {
+    let rendered = render("let number = 0\n\nlet NUMBER = 1");
+    let expected = "\
+error[Y0001]: variable `NUMBER` conflicts with existing variable `number`
+ --> test.yolk:3:1
+  |
+1 | let number = 0
+  | -------------- `number` defined here
+...
+3 | let NUMBER = 1
+  | ^^^^^^^^^^^^^^ conflicting name
+  |
+  = note: Yolol identifiers are case-insensitive
+";
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn test_render_mismatched_arrays() {
+    let rendered = render("let a = [1, 2] + [3, 4, 5]");
+    let expected = "\
+error[Y0014]: mismatched array lengths
+ --> test.yolk:1:9
+  |
+1 | let a = [1, 2] + [3, 4, 5]
+  |         ^^^^^^^^^^^^^^^^^^ arrays of length 2 and 3
+  |
+  = note: arrays must have the same length to be combined
+";
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn test_render_builtin_wrong_number_of_args() {
+    let rendered = render("let a = select(1, 2)");
+    let expected = "\
+error[Y0013]: function `select` takes 3 argument(s), but 2 were provided
+ --> test.yolk:1:9
+  |
+1 | let a = select(1, 2)
+  |         ^^^^^^^^^^^^ expected 3 argument(s)
+";
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn test_render_invalid_argument() {
+    let rendered = render("let a = fill(1, 0)");
+    let expected = "\
+error[Y0020]: invalid argument to `fill`
+ --> test.yolk:1:17
+  |
+1 | let a = fill(1, 0)
+  |                 ^ length must be positive, but got 0
+";
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn test_render_non_constant_argument() {
+    let rendered = render("import n\nlet a = fill(1, n)");
+    let expected = "\
+error[Y0026]: argument to `fill` is not a constant
+ --> test.yolk:2:17
+  |
+2 | let a = fill(1, n)
+  |                 ^ cannot be evaluated at compile time
+  |
+  = note: array lengths must be known at compile time
+";
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn test_render_prelude_error() {
+    let rendered = render("import x\nlet a = cross([1, 2], [x, x])");
+    let expected = "\
+error[Y0017]: index out of range
+ --> test.yolk:2:9
+  |
+2 | let a = cross([1, 2], [x, x])
+  |         ^^^^^^^^^^^^^^^^^^^^^ index 2 is out of range for array of length 2
+  |
+  = note: indices start at 0
+";
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn test_render_syntax_error() {
+    let rendered = render("let a = [0 1]");
+    assert!(rendered.starts_with("error[Y0002]: invalid syntax\n --> test.yolk:1:12\n"));
+}
+
+#[test]
+fn test_render_without_span() {
+    let error = YolkError::NestedArrays {
+        span: Default::default(),
+    };
+    let rendered = Diagnostic::from(&error).render("", "test.yolk");
+    let expected = "\
+error[Y0015]: cannot nest arrays
+ --> test.yolk
+  |
+  = note: arrays may only contain numbers
+";
+    assert_eq!(rendered, expected);
+}
+
+#[test]
+fn test_render_warning() {
+    let source = "import i\nlet a = [1, 2][i]";
+    let yolk: YolkProgram = source.parse().unwrap();
+    let (_, warnings) = transpile_with_warnings(yolk);
+    let rendered = Diagnostic::from(&warnings[0]).render(source, "test.yolk");
+    let expected = "\
+warning[W0001]: index is not a constant
+ --> test.yolk:2:9
+  |
+2 | let a = [1, 2][i]
+  |         ^^^^^^^^^ expands to 2 term(s) and 17 character(s)
+  |
+  = note: the index is compared to every position of the array at runtime
+  = note: an index that is out of range evaluates to 0
+";
+    assert_eq!(rendered, expected);
+}
@@ -1,43 +1,227 @@
+use crate::ast::Span;
+
 /// Represents a general error.
+///
+/// Every error carries the span of the source code that caused it.
 #[derive(Debug, Fail)]
 pub enum YolkError {
-    #[fail(display = "invalid syntax: {}", msg)]
-    InvalidSyntax { msg: String },
-
-    #[fail(display = "cannot import existing variable: {}", var)]
-    ImportExisting { var: String },
-    #[fail(display = "cannot import keyword: {}", var)]
-    ImportKeyword { var: String },
-
-    #[fail(display = "cannot define existing function: {}", func)]
-    DefineExisting { func: String },
-    #[fail(display = "cannot define keyword: {}", func)]
-    DefineKeyword { func: String },
-
-    #[fail(display = "cannot assign to existing variable: {}", var)]
-    AssignExisting { var: String },
-    #[fail(display = "cannot assign to keyword: {}", var)]
-    AssignKeyword { var: String },
-    //TODO: improve message
-    #[fail(display = "name conflict with variable: {}", var)]
-    AssignConflict { var: String },
-
-    #[fail(display = "undefined function: {}", func)]
-    UndefinedFunction { func: String },
-    #[fail(display = "undefined variable {}", var)]
-    UndefinedVariable { var: String },
-
-    #[fail(display = "duplicate parameters in function: {}", func)]
-    DuplicateParams { func: String },
-    #[fail(display = "recursive call in function: {}", func)]
-    RecursiveCall { func: String },
-    #[fail(display = "wrong number of arguments provided for function: {}", func)]
-    WrongNumberOfArgs { func: String },
-
-    //TODO: improve message
-    #[fail(display = "mismatched array lengths")]
-    MismatchedArrays,
-    //TODO: improve message
-    #[fail(display = "cannot nest arrays")]
-    NestedArrays,
+    #[fail(display = "{}: invalid syntax: {}", span, msg)]
+    InvalidSyntax { msg: String, span: Span },
+
+    #[fail(display = "{}: cannot import existing variable: {}", span, var)]
+    ImportExisting { var: String, span: Span, prev: Span },
+    #[fail(display = "{}: cannot import keyword: {}", span, var)]
+    ImportKeyword { var: String, span: Span },
+
+    #[fail(display = "{}: cannot define existing function: {}", span, func)]
+    DefineExisting {
+        func: String,
+        span: Span,
+        prev: Span,
+    },
+    #[fail(display = "{}: cannot define keyword: {}", span, func)]
+    DefineKeyword { func: String, span: Span },
+
+    #[fail(display = "{}: cannot assign to existing variable: {}", span, var)]
+    AssignExisting { var: String, span: Span, prev: Span },
+    #[fail(display = "{}: cannot assign to keyword: {}", span, var)]
+    AssignKeyword { var: String, span: Span },
+    #[fail(display = "{}: cannot set imported variable: {}", span, var)]
+    SetImport { var: String, span: Span, prev: Span },
+    #[fail(display = "{}: cannot export to existing name: {}", span, name)]
+    ExportExisting {
+        name: String,
+        span: Span,
+        prev: Span,
+    },
+    #[fail(
+        display = "{}: variable {} conflicts with existing variable: {}",
+        span, var, other
+    )]
+    AssignConflict {
+        var: String,
+        other: String,
+        span: Span,
+        prev: Span,
+    },
+
+    #[fail(display = "{}: undefined function: {}", span, func)]
+    UndefinedFunction { func: String, span: Span },
+    #[fail(display = "{}: undefined variable {}", span, var)]
+    UndefinedVariable { var: String, span: Span },
+
+    #[fail(display = "{}: duplicate parameters in function: {}", span, func)]
+    DuplicateParams { func: String, span: Span },
+    #[fail(display = "{}: recursive call in function: {}", span, func)]
+    RecursiveCall { func: String, span: Span },
+    #[fail(
+        display = "{}: function {} takes {} argument(s), but {} were provided",
+        span, func, expected, found
+    )]
+    WrongNumberOfArgs {
+        func: String,
+        expected: usize,
+        found: usize,
+        span: Span,
+        def: Option<Span>,
+    },
+
+    #[fail(
+        display = "{}: mismatched array lengths: {} and {}",
+        span, lhs_len, rhs_len
+    )]
+    MismatchedArrays {
+        lhs_len: usize,
+        rhs_len: usize,
+        span: Span,
+    },
+    #[fail(display = "{}: array elements cannot be arrays", span)]
+    NestedArrays { span: Span },
+
+    #[fail(display = "{}: malformed AST: {}", span, msg)]
+    MalformedAst { msg: String, span: Span },
+
+    #[fail(
+        display = "{}: index {} is out of range for array of length {}",
+        span, index, len
+    )]
+    IndexOutOfRange {
+        index: String,
+        len: usize,
+        span: Span,
+    },
+    #[fail(display = "{}: slice bounds must be constants", span)]
+    NonConstantIndex { span: Span },
+    #[fail(display = "{}: invalid index: {}", span, msg)]
+    InvalidIndex { msg: String, span: Span },
+    #[fail(display = "{}: cannot use {}: {}", span, path, msg)]
+    UseFailed {
+        path: String,
+        msg: String,
+        span: Span,
+    },
+    #[fail(display = "{}: cyclic use of {}", span, path)]
+    UseCycle { path: String, span: Span },
+    #[fail(display = "{}: used files may only contain definitions", span)]
+    LibraryStmt { span: Span },
+    #[fail(
+        display = "{}: cannot use {}: namespace {} is already used",
+        span, path, namespace
+    )]
+    NamespaceConflict {
+        path: String,
+        namespace: String,
+        span: Span,
+        prev: Span,
+    },
+
+    #[fail(display = "{}: invalid argument to {}: {}", span, func, msg)]
+    InvalidArgument {
+        func: String,
+        msg: String,
+        span: Span,
+    },
+    #[fail(display = "{}: argument to {} must be a constant", span, func)]
+    NonConstantArgument { func: String, span: Span },
+    #[fail(
+        display = "{}: {} compares {} numbers at runtime, but at most {} are allowed",
+        span, func, len, max
+    )]
+    LargeExtremum {
+        func: String,
+        len: usize,
+        max: usize,
+        span: Span,
+    },
+}
+
+impl YolkError {
+    /// Returns the span of the source code that caused an error.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::InvalidSyntax { span, .. }
+            | Self::ImportExisting { span, .. }
+            | Self::ImportKeyword { span, .. }
+            | Self::DefineExisting { span, .. }
+            | Self::DefineKeyword { span, .. }
+            | Self::AssignExisting { span, .. }
+            | Self::AssignKeyword { span, .. }
+            | Self::AssignConflict { span, .. }
+            | Self::SetImport { span, .. }
+            | Self::ExportExisting { span, .. }
+            | Self::UndefinedFunction { span, .. }
+            | Self::UndefinedVariable { span, .. }
+            | Self::DuplicateParams { span, .. }
+            | Self::RecursiveCall { span, .. }
+            | Self::WrongNumberOfArgs { span, .. }
+            | Self::MismatchedArrays { span, .. }
+            | Self::NestedArrays { span }
+            | Self::MalformedAst { span, .. }
+            | Self::IndexOutOfRange { span, .. }
+            | Self::NonConstantIndex { span }
+            | Self::InvalidIndex { span, .. }
+            | Self::InvalidArgument { span, .. }
+            | Self::NonConstantArgument { span, .. }
+            | Self::LargeExtremum { span, .. }
+            | Self::UseFailed { span, .. }
+            | Self::UseCycle { span, .. }
+            | Self::LibraryStmt { span }
+            | Self::NamespaceConflict { span, .. } => *span,
+        }
+    }
+
+    /// Returns the stable code of an error.
+    ///
+    /// Codes never change once assigned, so they can be searched for.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::AssignConflict { .. } => "Y0001",
+            Self::InvalidSyntax { .. } => "Y0002",
+            Self::ImportExisting { .. } => "Y0003",
+            Self::ImportKeyword { .. } => "Y0004",
+            Self::DefineExisting { .. } => "Y0005",
+            Self::DefineKeyword { .. } => "Y0006",
+            Self::AssignExisting { .. } => "Y0007",
+            Self::AssignKeyword { .. } => "Y0008",
+            Self::UndefinedFunction { .. } => "Y0009",
+            Self::UndefinedVariable { .. } => "Y0010",
+            Self::DuplicateParams { .. } => "Y0011",
+            Self::RecursiveCall { .. } => "Y0012",
+            Self::WrongNumberOfArgs { .. } => "Y0013",
+            Self::MismatchedArrays { .. } => "Y0014",
+            Self::NestedArrays { .. } => "Y0015",
+            Self::MalformedAst { .. } => "Y0016",
+            Self::IndexOutOfRange { .. } => "Y0017",
+            Self::NonConstantIndex { .. } => "Y0018",
+            Self::InvalidIndex { .. } => "Y0019",
+            Self::InvalidArgument { .. } => "Y0020",
+            Self::SetImport { .. } => "Y0021",
+            Self::UseFailed { .. } => "Y0022",
+            Self::UseCycle { .. } => "Y0023",
+            Self::LibraryStmt { .. } => "Y0024",
+            Self::ExportExisting { .. } => "Y0025",
+            Self::NonConstantArgument { .. } => "Y0026",
+            Self::LargeExtremum { .. } => "Y0027",
+            Self::NamespaceConflict { .. } => "Y0028",
+        }
+    }
+}
+
+/// Represents an error that prevents a Yolol program from being laid out on a chip.
+#[derive(Debug, Fail, PartialEq)]
+pub enum LayoutError {
+    #[fail(
+        display = "statement is {} characters long, but lines are limited to {}: {}",
+        len, width, stmt
+    )]
+    StmtTooLong {
+        stmt: String,
+        len: usize,
+        width: usize,
+    },
+    #[fail(
+        display = "program needs {} lines, but the chip only has {}",
+        lines, max_lines
+    )]
+    TooManyLines { lines: usize, max_lines: usize },
 }
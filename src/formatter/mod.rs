@@ -0,0 +1,148 @@
+use crate::ast::YolkStmt;
+use crate::error::YolkError;
+use crate::parser::parse;
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum width of a formatted statement, not including comments.
+const MAX_WIDTH: usize = 80;
+
+/// The indentation of a line-broken function body.
+const INDENT: &str = "    ";
+
+/// Represents a formatted line, which may have code, a comment, or neither.
+struct Line {
+    code: String,
+    comment: Option<String>,
+}
+
+/// Formats a Yolk source in the canonical style.
+///
+/// Comments are kept on their lines, and trailing comments on consecutive lines
+/// are aligned. Runs of blank lines are collapsed into a single blank line.
+pub fn format(source: &str) -> Result<String, Vec<YolkError>> {
+    let program = parse(source)?;
+    let mut lines = Vec::new();
+    // Stores the byte offset of the first line that has not been formatted
+    let mut pos = 0;
+    for stmt in program.into_iter() {
+        let span = stmt.span();
+        let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+        push_loose_lines(&source[pos..line_start], &mut lines);
+        // Statements cannot contain comments, so a comment can only follow the end
+        let line_end = source[span.end..]
+            .find('\n')
+            .map_or(source.len(), |i| span.end + i + 1);
+        let comment = source[span.end..line_end].trim();
+        let formatted = format_stmt(&stmt);
+        let mut stmt_lines: Vec<&str> = formatted.lines().collect();
+        let last = stmt_lines.pop().unwrap_or("");
+        for code in stmt_lines.into_iter() {
+            lines.push(Line {
+                code: code.to_string(),
+                comment: None,
+            });
+        }
+        lines.push(Line {
+            code: last.to_string(),
+            comment: if comment.is_empty() {
+                None
+            } else {
+                Some(comment.to_string())
+            },
+        });
+        pos = line_end;
+    }
+    push_loose_lines(&source[pos..], &mut lines);
+    Ok(render(&lines))
+}
+
+/// Formats a statement, breaking long function bodies onto their own line.
+fn format_stmt(stmt: &YolkStmt) -> String {
+    let line = stmt.to_string();
+    match stmt {
+        YolkStmt::Define {
+            ident,
+            params,
+            body,
+            ..
+        } if line.len() > MAX_WIDTH => format!(
+            "define {}({}) = (\n{}{}\n)",
+            ident,
+            params.join(", "),
+            INDENT,
+            body
+        ),
+        _ => line,
+    }
+}
+
+/// Adds lines that are outside of every statement, which are comments or blank.
+fn push_loose_lines(text: &str, lines: &mut Vec<Line>) {
+    for line in text.lines() {
+        let comment = line.trim();
+        lines.push(Line {
+            code: String::new(),
+            comment: if comment.is_empty() {
+                None
+            } else {
+                Some(comment.to_string())
+            },
+        });
+    }
+}
+
+/// Renders formatted lines, aligning the trailing comments of consecutive lines.
+fn render(lines: &[Line]) -> String {
+    let mut buffer = String::new();
+    let mut blank = false;
+    for (i, line) in lines.iter().enumerate() {
+        match (line.code.is_empty(), &line.comment) {
+            (true, None) => {
+                blank = true;
+                continue;
+            }
+            (true, Some(comment)) => {
+                push_line(&mut buffer, comment, blank);
+            }
+            (false, None) => push_line(&mut buffer, &line.code, blank),
+            (false, Some(comment)) => {
+                let width = aligned_width(lines, i);
+                let code = format!("{:width$} {}", line.code, comment, width = width);
+                push_line(&mut buffer, &code, blank);
+            }
+        }
+        blank = false;
+    }
+    buffer
+}
+
+/// Adds a line to a buffer, after a blank line if needed.
+///
+/// Blank lines are never added at the start of the buffer.
+fn push_line(buffer: &mut String, line: &str, blank: bool) {
+    if blank && !buffer.is_empty() {
+        buffer.push('\n');
+    }
+    buffer.push_str(line);
+    buffer.push('\n');
+}
+
+/// Finds the width of the code in a run of lines with trailing comments.
+fn aligned_width(lines: &[Line], index: usize) -> usize {
+    let has_trailing = |line: &Line| !line.code.is_empty() && line.comment.is_some();
+    let start = lines[..index]
+        .iter()
+        .rposition(|l| !has_trailing(l))
+        .map_or(0, |i| i + 1);
+    let end = lines[index..]
+        .iter()
+        .position(|l| !has_trailing(l))
+        .map_or(lines.len(), |i| index + i);
+    lines[start..end]
+        .iter()
+        .map(|l| l.code.chars().count())
+        .max()
+        .unwrap_or(0)
+}
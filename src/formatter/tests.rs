@@ -0,0 +1,69 @@
+use crate::error::YolkError;
+use crate::formatter::format;
+
+#[test]
+fn test_format_spacing() -> Result<(), Vec<YolkError>> {
+    let source = "import a\nlet b=(a+1)*2\ndefine f(A,B)=A^B\nexport b as c\n";
+    let expected = "import a\nlet b = (a + 1) * 2\ndefine f(A, B) = A ^ B\nexport b as c\n";
+    assert_eq!(format(source)?, expected);
+    Ok(())
+}
+
+#[test]
+fn test_format_blank_lines() -> Result<(), Vec<YolkError>> {
+    let source = "\n\nlet a = 1\n\n\n\nlet b = 2\n   \n";
+    assert_eq!(format(source)?, "let a = 1\n\nlet b = 2\n");
+    Ok(())
+}
+
+#[test]
+fn test_format_comments() -> Result<(), Vec<YolkError>> {
+    let source = "  // Header\nlet a = 1 //one\n\n// Footer  ";
+    assert_eq!(format(source)?, "// Header\nlet a = 1 //one\n\n// Footer\n");
+    Ok(())
+}
+
+#[test]
+fn test_format_aligned_comments() -> Result<(), Vec<YolkError>> {
+    let source = "let a = 1 // one\nlet bar = 22 // two\n// three\nlet c = 3    // four\n";
+    let expected = "let a = 1    // one\nlet bar = 22 // two\n// three\nlet c = 3 // four\n";
+    assert_eq!(format(source)?, expected);
+    Ok(())
+}
+
+#[test]
+fn test_format_comment_in_path() -> Result<(), Vec<YolkError>> {
+    let source = "use \"lib//vector.yolk\" // vectors\n";
+    assert_eq!(format(source)?, source);
+    Ok(())
+}
+
+#[test]
+fn test_format_long_define() -> Result<(), Vec<YolkError>> {
+    let source = "define norm(Vector, Other) = sqrt(sum(Vector * Vector)) + sqrt(sum(Other * Other)) // norms";
+    let expected = "define norm(Vector, Other) = (\n    sqrt(sum(Vector * Vector)) + sqrt(sum(Other * Other))\n) // norms\n";
+    assert_eq!(format(source)?, expected);
+    assert_eq!(format(expected)?, expected);
+    Ok(())
+}
+
+#[test]
+fn test_format_multiline_stmt() -> Result<(), Vec<YolkError>> {
+    let source = "let a = sum(\n  1,\n  2\n) // total\nlet b = 1\n";
+    assert_eq!(format(source)?, "let a = sum(1, 2) // total\nlet b = 1\n");
+    Ok(())
+}
+
+#[test]
+fn test_format_idempotence() -> Result<(), Vec<YolkError>> {
+    let source =
+        "// Vectors\n\ndefine dot(V,W)=sum(V*W) // dot\nlet foo=dot([1,2],[3,4])\n\n\n// Result";
+    let once = format(source)?;
+    assert_eq!(format(&once)?, once);
+    Ok(())
+}
+
+#[test]
+fn test_format_errors() {
+    assert!(format("let a = 1\nlet b =").is_err());
+}
@@ -0,0 +1,245 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::ast::{YololProgram, YololStmt};
+use crate::error::LayoutError;
+use crate::optimizer::{collect_idents, is_total};
+
+mod partition;
+#[cfg(test)]
+mod tests;
+
+pub use partition::{partition, Partition};
+
+/// Represents the size of the lines and the number of lines on a Yolol chip.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChipLimits {
+    /// The maximum number of characters on a line.
+    pub width: usize,
+    /// The maximum number of lines.
+    pub lines: usize,
+}
+
+impl ChipLimits {
+    /// The limits of a basic Yolol chip.
+    pub const BASIC: ChipLimits = ChipLimits {
+        width: 70,
+        lines: 12,
+    };
+    /// The limits of an advanced Yolol chip.
+    pub const ADVANCED: ChipLimits = ChipLimits {
+        width: 70,
+        lines: 16,
+    };
+    /// The limits of a professional Yolol chip.
+    pub const PROFESSIONAL: ChipLimits = ChipLimits {
+        width: 70,
+        lines: 20,
+    };
+}
+
+impl Default for ChipLimits {
+    /// Returns the limits of the largest Yolol chip.
+    fn default() -> Self {
+        Self::PROFESSIONAL
+    }
+}
+
+/// Represents the statements on each line of a Yolol chip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChipLayout {
+    lines: Vec<Vec<YololStmt>>,
+}
+
+impl ChipLayout {
+    /// Returns the statements on each line.
+    pub fn lines(&self) -> &[Vec<YololStmt>] {
+        &self.lines
+    }
+
+    /// Returns the number of lines.
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Returns whether a layout has no lines.
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+}
+
+impl From<ChipLayout> for YololProgram {
+    /// Converts a layout to a program with the statements in line order.
+    fn from(layout: ChipLayout) -> Self {
+        layout
+            .lines
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .into()
+    }
+}
+
+impl fmt::Display for ChipLayout {
+    /// Formats a layout as a string, with one line of Yolol per line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<String> = self
+            .lines
+            .iter()
+            .map(|line| {
+                let stmts: Vec<String> = line.iter().map(|s| s.to_string()).collect();
+                stmts.join(" ")
+            })
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Lays out statements on the lines of a chip.
+///
+/// Statements are reordered if it saves lines, but only if they do not depend
+/// on each other, and only if they cannot cause runtime errors.
+pub fn layout(stmts: &[YololStmt], limits: &ChipLimits) -> Result<ChipLayout, LayoutError> {
+    layout_with_dependencies(stmts, &dependencies(stmts), limits)
+}
+
+/// Lays out statements on the lines of a chip, given the earlier statements
+/// that each statement depends on.
+pub(crate) fn layout_with_dependencies(
+    stmts: &[YololStmt],
+    deps: &[Vec<usize>],
+    limits: &ChipLimits,
+) -> Result<ChipLayout, LayoutError> {
+    let lens: Vec<usize> = stmts.iter().map(|s| s.to_string().len()).collect();
+    if let Some(index) = lens.iter().position(|&len| len > limits.width) {
+        return Err(LayoutError::StmtTooLong {
+            stmt: stmts[index].to_string(),
+            len: lens[index],
+            width: limits.width,
+        });
+    }
+    let in_order = pack_lines(&lens, limits.width);
+    let reordered = schedule_lines(deps, &lens, limits.width);
+    let lines = if reordered.len() < in_order.len() {
+        reordered
+    } else {
+        in_order
+    };
+    if lines.len() > limits.lines {
+        return Err(LayoutError::TooManyLines {
+            lines: lines.len(),
+            max_lines: limits.lines,
+        });
+    }
+    Ok(ChipLayout {
+        lines: lines
+            .into_iter()
+            .map(|line| line.into_iter().map(|i| stmts[i].clone()).collect())
+            .collect(),
+    })
+}
+
+/// Lays out statements in order, without any limits.
+///
+/// Statements that are too long to share a line are put on their own line.
+pub fn pack(stmts: &[YololStmt], width: usize) -> ChipLayout {
+    let lens: Vec<usize> = stmts.iter().map(|s| s.to_string().len()).collect();
+    ChipLayout {
+        lines: pack_lines(&lens, width)
+            .into_iter()
+            .map(|line| line.into_iter().map(|i| stmts[i].clone()).collect())
+            .collect(),
+    }
+}
+
+/// Packs statements into lines in order, starting a line whenever a statement does not fit.
+///
+/// Returns the indices of the statements on each line.
+fn pack_lines(lens: &[usize], width: usize) -> Vec<Vec<usize>> {
+    let mut lines: Vec<Vec<usize>> = Vec::new();
+    let mut used = 0;
+    for (index, &len) in lens.iter().enumerate() {
+        match lines.last_mut() {
+            // Statements on the same line are separated by a space
+            Some(line) if used + 1 + len <= width => {
+                line.push(index);
+                used += 1 + len;
+            }
+            _ => {
+                lines.push(vec![index]);
+                used = len;
+            }
+        }
+    }
+    lines
+}
+
+/// Packs statements into lines, filling each line with the longest statements that fit.
+///
+/// A statement is only placed after every earlier statement that it depends on.
+///
+/// Returns the indices of the statements on each line.
+fn schedule_lines(deps: &[Vec<usize>], lens: &[usize], width: usize) -> Vec<Vec<usize>> {
+    let mut placed = vec![false; lens.len()];
+    let mut remaining = lens.len();
+    let mut lines = Vec::new();
+    while remaining > 0 {
+        let mut line = Vec::new();
+        let mut used = 0;
+        loop {
+            let sep = if line.is_empty() { 0 } else { 1 };
+            // The earliest remaining statement is always ready, so every line has a statement
+            let next = (0..lens.len())
+                .filter(|&i| !placed[i] && deps[i].iter().all(|&d| placed[d]))
+                .filter(|&i| used + sep + lens[i] <= width)
+                .max_by_key(|&i| (lens[i], std::cmp::Reverse(i)));
+            match next {
+                Some(index) => {
+                    placed[index] = true;
+                    remaining -= 1;
+                    used += sep + lens[index];
+                    line.push(index);
+                }
+                None => break,
+            }
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+/// Finds the earlier statements that each statement depends on.
+///
+/// A statement depends on an earlier statement if either one assigns a variable
+/// that the other reads or assigns. Statements that can cause a runtime error
+/// depend on every earlier statement, and every later statement depends on them.
+pub(crate) fn dependencies(stmts: &[YololStmt]) -> Vec<Vec<usize>> {
+    let effects: Vec<(String, HashSet<String>, bool)> = stmts
+        .iter()
+        .map(|stmt| match stmt {
+            YololStmt::Assign { ident, expr } => {
+                let mut reads = HashSet::new();
+                collect_idents(expr, &mut reads);
+                (ident.to_lowercase(), reads, is_total(expr))
+            }
+        })
+        .collect();
+    effects
+        .iter()
+        .enumerate()
+        .map(|(index, (assign, reads, total))| {
+            effects[..index]
+                .iter()
+                .enumerate()
+                .filter(|(_, (other_assign, other_reads, other_total))| {
+                    !total
+                        || !other_total
+                        || assign == other_assign
+                        || reads.contains(other_assign)
+                        || other_reads.contains(assign)
+                })
+                .map(|(other, _)| other)
+                .collect()
+        })
+        .collect()
+}
@@ -0,0 +1,256 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::ast::YololStmt;
+use crate::error::LayoutError;
+use crate::layout::{dependencies, layout_with_dependencies, ChipLayout, ChipLimits};
+use crate::minifier::{rename_expr, NameMap};
+use crate::optimizer::collect_idents;
+
+/// Represents a Yolol program that is split across chips.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Partition {
+    chips: Vec<ChipLayout>,
+    fields: NameMap,
+}
+
+impl Partition {
+    /// Returns the layout of each chip.
+    pub fn chips(&self) -> &[ChipLayout] {
+        &self.chips
+    }
+
+    /// Returns the number of chips.
+    pub fn len(&self) -> usize {
+        self.chips.len()
+    }
+
+    /// Returns whether a partition has no chips.
+    pub fn is_empty(&self) -> bool {
+        self.chips.is_empty()
+    }
+
+    /// Returns the data fields that replace the variables shared between chips.
+    pub fn fields(&self) -> &NameMap {
+        &self.fields
+    }
+}
+
+impl fmt::Display for Partition {
+    /// Formats a partition as a string, with a comment before each chip.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let chips: Vec<String> = self
+            .chips
+            .iter()
+            .enumerate()
+            .map(|(i, chip)| format!("// chip {} of {}\n{}", i + 1, self.chips.len(), chip))
+            .collect();
+        write!(f, "{}", chips.join("\n\n"))
+    }
+}
+
+/// Splits statements across as few chips as possible.
+///
+/// Statements that do not depend on each other are grouped, then each chip is
+/// filled with as many consecutive statements as fit. Variables that are assigned
+/// on one chip and used on another are replaced with data fields (e.g. `:foo`).
+pub fn partition(stmts: &[YololStmt], limits: &ChipLimits) -> Result<Partition, LayoutError> {
+    let deps = dependencies(stmts);
+    let order = group_components(&deps);
+    let mut positions = vec![0; order.len()];
+    for (position, &index) in order.iter().enumerate() {
+        positions[index] = position;
+    }
+    let stmts: Vec<YololStmt> = order.iter().map(|&i| stmts[i].clone()).collect();
+    // Statements only depend on statements in their group, which keep their order
+    let deps: Vec<Vec<usize>> = order
+        .iter()
+        .map(|&i| {
+            let mut deps: Vec<usize> = deps[i].iter().map(|&d| positions[d]).collect();
+            deps.sort();
+            deps
+        })
+        .collect();
+    let fields = field_names(&stmts);
+    // Maps each variable that could become a field to the indices of the statements that use it
+    let mut uses: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, stmt) in stmts.iter().enumerate() {
+        for var in used_vars(stmt).into_iter() {
+            if fields.contains_key(&var) {
+                uses.entry(var).or_insert_with(Vec::new).push(index);
+            }
+        }
+    }
+    // A variable is shared if it is used outside of the statements on a chip
+    let shared = |start: usize, end: usize| -> HashSet<String> {
+        uses.iter()
+            .filter(|(_, indices)| {
+                indices.iter().any(|&i| start <= i && i < end)
+                    && indices.iter().any(|&i| i < start || end <= i)
+            })
+            .map(|(var, _)| var.to_string())
+            .collect()
+    };
+    // Lays out the statements from start to end on one chip
+    let fit = |start: usize, end: usize| {
+        let vars = shared(start, end);
+        let chip_stmts = replace_vars(&stmts[start..end], &vars, &fields);
+        let chip_deps: Vec<Vec<usize>> = deps[start..end]
+            .iter()
+            .map(|deps| {
+                deps.iter()
+                    .filter(|&&d| d >= start)
+                    .map(|&d| d - start)
+                    .collect()
+            })
+            .collect();
+        layout_with_dependencies(&chip_stmts, &chip_deps, limits).map(|chip| (chip, vars))
+    };
+    // Replacing variables with fields only makes statements longer, so
+    // statements that are longer than a chip in total can never fit on it
+    let lens: Vec<usize> = stmts.iter().map(|s| s.to_string().len()).collect();
+    let capacity = limits.width.saturating_mul(limits.lines);
+    let mut chips = Vec::new();
+    let mut renamed = HashSet::new();
+    let mut start = 0;
+    while start < stmts.len() {
+        // If even one statement does not fit, no partition exists
+        let mut best = (start + 1, fit(start, start + 1)?);
+        let mut total = lens[start];
+        let mut max_end = start + 1;
+        while max_end < stmts.len() && total + lens[max_end] <= capacity {
+            total += lens[max_end];
+            max_end += 1;
+        }
+        // Fewer statements fit unless a variable becomes a longer data field, so
+        // a binary search finds the last end that fits, or one close to it
+        let (mut lo, mut hi) = (start + 1, max_end);
+        while lo < hi {
+            let mid = (lo + hi + 1) / 2;
+            match fit(start, mid) {
+                Ok(result) => {
+                    best = (mid, result);
+                    lo = mid;
+                }
+                Err(_) => hi = mid - 1,
+            }
+        }
+        let (end, (chip, vars)) = best;
+        chips.push(chip);
+        renamed.extend(vars);
+        start = end;
+    }
+    let mut map = NameMap::default();
+    let mut seen = HashSet::new();
+    for stmt in stmts.iter() {
+        match stmt {
+            YololStmt::Assign { ident, .. } => {
+                let var = ident.to_lowercase();
+                if renamed.contains(&var) && seen.insert(var.clone()) {
+                    map.insert(ident.to_string(), fields[&var].to_string());
+                }
+            }
+        }
+    }
+    Ok(Partition { chips, fields: map })
+}
+
+/// Orders statements so that statements which depend on each other are consecutive.
+///
+/// Groups are ordered by their first statement, and statements within a group keep their order.
+///
+/// Returns the indices of the statements in their new order.
+fn group_components(deps: &[Vec<usize>]) -> Vec<usize> {
+    fn find(parents: &mut Vec<usize>, i: usize) -> usize {
+        let mut root = i;
+        while parents[root] != root {
+            root = parents[root];
+        }
+        parents[i] = root;
+        root
+    }
+    let mut parents: Vec<usize> = (0..deps.len()).collect();
+    for (index, deps) in deps.iter().enumerate() {
+        for &dep in deps.iter() {
+            let (a, b) = (find(&mut parents, index), find(&mut parents, dep));
+            // The root of each group is its first statement
+            parents[a.max(b)] = a.min(b);
+        }
+    }
+    let mut order: Vec<usize> = (0..deps.len()).collect();
+    // Sorting is stable, so statements within a group keep their order
+    order.sort_by_key(|&i| find(&mut parents, i));
+    order
+}
+
+/// Chooses a data field name for every variable that is assigned.
+///
+/// Returns a map from lowercase variable names to field names.
+fn field_names(stmts: &[YololStmt]) -> HashMap<String, String> {
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut vars: Vec<String> = Vec::new();
+    for stmt in stmts.iter() {
+        for var in used_vars(stmt).into_iter() {
+            if var.starts_with(':') {
+                taken.insert(var);
+            }
+        }
+        match stmt {
+            YololStmt::Assign { ident, .. } => {
+                let var = ident.to_lowercase();
+                if !var.starts_with(':') && !vars.contains(&var) {
+                    vars.push(var);
+                }
+            }
+        }
+    }
+    let mut fields = HashMap::new();
+    for var in vars.into_iter() {
+        // Fields that are already used keep their names, so new fields get a suffix if needed
+        let mut field = format!(":{}", var);
+        let mut suffix = 0;
+        while taken.contains(&field) {
+            suffix += 1;
+            field = format!(":{}{}", var, suffix);
+        }
+        taken.insert(field.clone());
+        fields.insert(var, field);
+    }
+    fields
+}
+
+/// Returns the lowercase variables that a statement assigns or reads.
+fn used_vars(stmt: &YololStmt) -> HashSet<String> {
+    match stmt {
+        YololStmt::Assign { ident, expr } => {
+            let mut vars = HashSet::new();
+            collect_idents(expr, &mut vars);
+            vars.insert(ident.to_lowercase());
+            vars
+        }
+    }
+}
+
+/// Replaces variables with their data fields.
+fn replace_vars(
+    stmts: &[YololStmt],
+    vars: &HashSet<String>,
+    fields: &HashMap<String, String>,
+) -> Vec<YololStmt> {
+    let rename = |ident: String| {
+        if vars.contains(&ident.to_lowercase()) {
+            fields[&ident.to_lowercase()].to_string()
+        } else {
+            ident
+        }
+    };
+    stmts
+        .iter()
+        .map(|stmt| match stmt {
+            YololStmt::Assign { ident, expr } => YololStmt::Assign {
+                ident: rename(ident.to_string()),
+                expr: Box::new(rename_expr((**expr).clone(), &rename)),
+            },
+        })
+        .collect()
+}
@@ -0,0 +1,182 @@
+use crate::ast::YololProgram;
+use crate::error::{LayoutError, YolkError};
+use crate::layout::ChipLimits;
+
+fn limits(width: usize, lines: usize) -> ChipLimits {
+    ChipLimits { width, lines }
+}
+
+#[test]
+fn test_layout_in_order() -> Result<(), Vec<YolkError>> {
+    let yolol: YololProgram = "a=1 b=2 c=3 d=4".parse()?;
+    let layout = yolol.layout(&limits(7, 20)).unwrap();
+    assert_eq!(layout.len(), 2);
+    assert_eq!(layout.to_string(), "a=1 b=2\nc=3 d=4");
+    Ok(())
+}
+
+#[test]
+fn test_layout_reorders_independent_stmts() -> Result<(), Vec<YolkError>> {
+    let yolol: YololProgram = "a=1234567 b=1 c=1234567 d=1".parse()?;
+    let layout = yolol.layout(&limits(10, 20)).unwrap();
+    assert_eq!(layout.to_string(), "a=1234567\nc=1234567\nb=1 d=1");
+    Ok(())
+}
+
+#[test]
+fn test_layout_keeps_dependencies() -> Result<(), Vec<YolkError>> {
+    // b=1234567 must stay after a=b, which reads the previous value of b
+    let yolol: YololProgram = "a=b b=1234567 c=12".parse()?;
+    let layout = yolol.layout(&limits(9, 20)).unwrap();
+    assert_eq!(layout.to_string(), "c=12 a=b\nb=1234567");
+    Ok(())
+}
+
+#[test]
+fn test_layout_keeps_runtime_errors() -> Result<(), Vec<YolkError>> {
+    // c=1/d may skip the rest of its line, so it must stay after b=1
+    let yolol: YololProgram = "b=1 a=1234567 c=1/d".parse()?;
+    let layout = yolol.layout(&limits(9, 20)).unwrap();
+    assert_eq!(layout.to_string(), "a=1234567\nb=1 c=1/d");
+    Ok(())
+}
+
+#[test]
+fn test_layout_prefers_order() -> Result<(), Vec<YolkError>> {
+    let yolol: YololProgram = "a=1 b=12 c=1234567".parse()?;
+    let layout = yolol.layout(&limits(9, 20)).unwrap();
+    assert_eq!(layout.to_string(), "a=1 b=12\nc=1234567");
+    Ok(())
+}
+
+#[test]
+fn test_layout_stmt_too_long() -> Result<(), Vec<YolkError>> {
+    let yolol: YololProgram = "a=1 b=123456789".parse()?;
+    assert_eq!(
+        yolol.layout(&limits(10, 20)),
+        Err(LayoutError::StmtTooLong {
+            stmt: "b=123456789".to_string(),
+            len: 11,
+            width: 10,
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_layout_too_many_lines() -> Result<(), Vec<YolkError>> {
+    let yolol: YololProgram = "a=1 b=2 c=3".parse()?;
+    assert_eq!(
+        yolol.layout(&limits(3, 2)),
+        Err(LayoutError::TooManyLines {
+            lines: 3,
+            max_lines: 2,
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_layout_chips() -> Result<(), Vec<YolkError>> {
+    let stmt = format!("a=a+{}", "b".repeat(60));
+    let yolol: YololProgram = vec![stmt; 14].join(" ").parse()?;
+    assert!(yolol.layout(&ChipLimits::BASIC).is_err());
+    assert_eq!(yolol.layout(&ChipLimits::ADVANCED).unwrap().len(), 14);
+    assert_eq!(yolol.layout(&ChipLimits::default()).unwrap().len(), 14);
+    Ok(())
+}
+
+#[test]
+fn test_layout_to_program() -> Result<(), Vec<YolkError>> {
+    let yolol: YololProgram = "a=b b=1234567 c=12".parse()?;
+    let layout = yolol.layout(&limits(9, 20)).unwrap();
+    let reordered: YololProgram = "c=12 a=b b=1234567".parse()?;
+    assert_eq!(YololProgram::from(layout), reordered);
+    Ok(())
+}
+
+#[test]
+fn test_display_long_stmt() -> Result<(), Vec<YolkError>> {
+    let long = format!("b={}", "c".repeat(70));
+    let yolol: YololProgram = format!("{} a=1", long).parse()?;
+    assert_eq!(yolol.to_string(), format!("{}\na=1", long));
+    Ok(())
+}
+
+#[test]
+fn test_partition_fits() -> Result<(), Vec<YolkError>> {
+    let yolol: YololProgram = "a=1 b=a+1".parse()?;
+    let partition = yolol.partition(&limits(10, 1)).unwrap();
+    assert_eq!(partition.len(), 1);
+    assert!(partition.fields().is_empty());
+    assert_eq!(partition.to_string(), "// chip 1 of 1\na=1 b=a+1");
+    Ok(())
+}
+
+#[test]
+fn test_partition_shared_vars() -> Result<(), Vec<YolkError>> {
+    let yolol: YololProgram = "a=1 b=a+1 c=b*2".parse()?;
+    let partition = yolol.partition(&limits(10, 1)).unwrap();
+    assert_eq!(partition.len(), 2);
+    assert_eq!(partition.fields().get("a"), None);
+    assert_eq!(partition.fields().get("b"), Some(":b"));
+    assert_eq!(
+        partition.to_string(),
+        "// chip 1 of 2\na=1 :b=a+1\n\n// chip 2 of 2\nc=:b*2"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_partition_groups_independent_stmts() -> Result<(), Vec<YolkError>> {
+    let yolol: YololProgram = "a=1 x=2 b=a+1 y=x+1".parse()?;
+    let partition = yolol.partition(&limits(9, 1)).unwrap();
+    assert!(partition.fields().is_empty());
+    assert_eq!(
+        partition.to_string(),
+        "// chip 1 of 2\na=1 b=a+1\n\n// chip 2 of 2\nx=2 y=x+1"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_partition_existing_fields() -> Result<(), Vec<YolkError>> {
+    let yolol: YololProgram = "a=:a+1 :out=a*2".parse()?;
+    let partition = yolol.partition(&limits(10, 1)).unwrap();
+    assert_eq!(partition.fields().get("a"), Some(":a1"));
+    assert_eq!(
+        partition.to_string(),
+        "// chip 1 of 2\n:a1=:a+1\n\n// chip 2 of 2\n:out=:a1*2"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_partition_errors() -> Result<(), Vec<YolkError>> {
+    let yolol: YololProgram = "a=1 b=123456789".parse()?;
+    assert_eq!(
+        yolol.partition(&limits(10, 1)),
+        Err(LayoutError::StmtTooLong {
+            stmt: "b=123456789".to_string(),
+            len: 11,
+            width: 10,
+        })
+    );
+    Ok(())
+}
+
+#[test]
+fn test_partition_many_stmts() -> Result<(), Vec<YolkError>> {
+    let source: Vec<String> = (0..500).map(|i| format!("v{}=v{}+1", i, i / 2)).collect();
+    let yolol: YololProgram = source.join(" ").parse()?;
+    let partition = yolol.partition(&ChipLimits::BASIC).unwrap();
+    assert!(partition.len() > 1);
+    assert!(partition.chips().iter().all(|chip| chip.len() <= 12));
+    let stmts: usize = partition
+        .chips()
+        .iter()
+        .map(|chip| chip.lines().iter().map(Vec::len).sum::<usize>())
+        .sum();
+    assert_eq!(stmts, 500);
+    Ok(())
+}
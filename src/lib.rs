@@ -18,6 +18,15 @@
 //! // Print a Yolol program as a chip
 //! println!("{}", optimized.to_string());
 //! ```
+//!
+//! Hand-written Yolol can be optimized too, as long as it only contains assignments:
+//!
+//! ```
+//! use yolk::YololProgram;
+//!
+//! let yolol: YololProgram = "a=1+2 :out=a*b".parse().unwrap();
+//! assert_eq!(yolol.optimize().to_string(), "a=3 :out=3*b");
+//! ```
 
 #[macro_use]
 extern crate failure;
@@ -28,10 +37,17 @@ extern crate pest;
 extern crate pest_derive;
 
 pub mod ast;
+pub mod diagnostic;
 pub mod error;
+pub mod formatter;
+pub mod layout;
+pub mod loader;
+pub mod minifier;
 pub mod optimizer;
 pub mod parser;
 pub mod transpiler;
+pub mod warning;
 
 pub use ast::{YolkProgram, YololProgram};
-pub use error::YolkError;
+pub use error::{LayoutError, YolkError};
+pub use warning::YolkWarning;
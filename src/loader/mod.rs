@@ -0,0 +1,232 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::ast::{Span, YolkExpr, YolkProgram, YolkStmt};
+use crate::error::YolkError;
+use crate::parser;
+
+#[cfg(test)]
+mod tests;
+
+/// Represents a Yolk source file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceFile {
+    /// The path of the file, relative to the working directory.
+    pub path: PathBuf,
+    /// The contents of the file.
+    pub source: String,
+}
+
+/// Loads Yolk programs that are split across multiple files.
+///
+/// Each `use` statement is replaced with the definitions from the used file,
+/// which are namespaced by the name of the file (e.g. `vector::dot`). Paths
+/// are resolved relative to the file that contains the `use` statement.
+///
+/// A file is only loaded once, no matter how many times it is used. Used files
+/// must have different names, because they would share a namespace.
+#[derive(Debug, Default)]
+pub struct Loader {
+    // Stores the files that have been read
+    // The index of a file is stored in the spans of its statements
+    files: Vec<SourceFile>,
+    // Stores the canonical paths of the files that have been loaded
+    loaded: HashSet<PathBuf>,
+    // Stores the canonical paths of the files that are being loaded
+    // Used for detecting cycles
+    loading: Vec<PathBuf>,
+    // Maps the namespaces of used files to the spans of the statements that used them
+    namespaces: HashMap<String, Span>,
+}
+
+impl Loader {
+    /// Creates an empty loader.
+    pub fn new() -> Loader {
+        Loader::default()
+    }
+
+    /// Returns the files that have been read, in the order of their indices.
+    pub fn files(&self) -> &[SourceFile] {
+        &self.files
+    }
+
+    /// Loads a Yolk program from the source of its main file.
+    ///
+    /// Every error in the program and the files that it uses is returned.
+    pub fn load(&mut self, path: &Path, source: &str) -> Result<YolkProgram, Vec<YolkError>> {
+        let canonical = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+        self.loading.push(canonical.clone());
+        let result = self.load_source(path, source, None);
+        self.loading.pop();
+        self.loaded.insert(canonical);
+        result.map(|stmts| stmts.into())
+    }
+
+    /// Parses a file and replaces its `use` statements with definitions.
+    ///
+    /// The definitions in a used file are namespaced, and used files must not
+    /// contain any other statements.
+    fn load_source(
+        &mut self,
+        path: &Path,
+        source: &str,
+        namespace: Option<&str>,
+    ) -> Result<Vec<YolkStmt>, Vec<YolkError>> {
+        let file = self.files.len();
+        self.files.push(SourceFile {
+            path: path.to_path_buf(),
+            source: source.to_string(),
+        });
+        let mut stmts: Vec<YolkStmt> = parser::parse(source)
+            .map_err(|errors| {
+                errors
+                    .into_iter()
+                    .map(|e| in_file(e, file))
+                    .collect::<Vec<_>>()
+            })?
+            .into_iter()
+            .collect();
+        for stmt in stmts.iter_mut() {
+            relocate(stmt, file);
+        }
+        if let Some(namespace) = namespace {
+            qualify(&mut stmts, namespace);
+        }
+        let dir = path.parent().unwrap_or_else(|| Path::new(""));
+        let mut resolved = Vec::new();
+        let mut errors = Vec::new();
+        for stmt in stmts.into_iter() {
+            match stmt {
+                YolkStmt::Use { path, span } => match self.load_used(dir, &path, &span) {
+                    Ok(stmts) => resolved.extend(stmts),
+                    Err(es) => errors.extend(es),
+                },
+                YolkStmt::Define { .. } => resolved.push(stmt),
+                _ if namespace.is_some() => {
+                    errors.push(YolkError::LibraryStmt { span: stmt.span() })
+                }
+                _ => resolved.push(stmt),
+            }
+        }
+        if errors.is_empty() {
+            Ok(resolved)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Loads a file that is used by another file in a directory.
+    ///
+    /// Nothing is loaded if the file has already been loaded.
+    fn load_used(
+        &mut self,
+        dir: &Path,
+        path: &str,
+        span: &Span,
+    ) -> Result<Vec<YolkStmt>, Vec<YolkError>> {
+        let failed = |msg: String| {
+            vec![YolkError::UseFailed {
+                path: path.to_string(),
+                msg,
+                span: *span,
+            }]
+        };
+        let file_path = dir.join(path);
+        let canonical = fs::canonicalize(&file_path).map_err(|e| failed(e.to_string()))?;
+        if self.loading.contains(&canonical) {
+            return Err(vec![YolkError::UseCycle {
+                path: path.to_string(),
+                span: *span,
+            }]);
+        }
+        if !self.loaded.insert(canonical.clone()) {
+            return Ok(Vec::new());
+        }
+        let namespace = file_path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .unwrap_or("");
+        if namespace.is_empty()
+            || !namespace
+                .chars()
+                .all(|c| c.is_ascii_alphabetic() || c == '_')
+        {
+            return Err(failed(format!(
+                "file name `{}` is not a valid namespace",
+                namespace
+            )));
+        }
+        if let Some(prev) = self.namespaces.get(namespace) {
+            return Err(vec![YolkError::NamespaceConflict {
+                path: path.to_string(),
+                namespace: namespace.to_string(),
+                span: *span,
+                prev: *prev,
+            }]);
+        }
+        self.namespaces.insert(namespace.to_string(), *span);
+        let source = fs::read_to_string(&canonical).map_err(|e| failed(e.to_string()))?;
+        self.loading.push(canonical);
+        let result = self.load_source(&file_path, &source, Some(namespace));
+        self.loading.pop();
+        result
+    }
+}
+
+/// Moves the spans of a statement into a file.
+fn relocate(stmt: &mut YolkStmt, file: usize) {
+    match stmt {
+        YolkStmt::Import { span, .. }
+        | YolkStmt::Use { span, .. }
+        | YolkStmt::Export { span, .. } => span.file = file,
+        YolkStmt::Define { body, span, .. }
+        | YolkStmt::Let {
+            expr: body, span, ..
+        }
+        | YolkStmt::Set {
+            expr: body, span, ..
+        } => {
+            span.file = file;
+            body.walk_mut(&mut |expr| expr.span_mut().file = file);
+        }
+    }
+}
+
+/// Moves the span of a syntax error into a file.
+///
+/// The parser only reports syntax errors, which have no secondary spans.
+fn in_file(error: YolkError, file: usize) -> YolkError {
+    match error {
+        YolkError::InvalidSyntax { msg, mut span } => {
+            span.file = file;
+            YolkError::InvalidSyntax { msg, span }
+        }
+        e => e,
+    }
+}
+
+/// Adds a namespace to the functions defined in a file.
+///
+/// Calls to functions defined in the same file are namespaced too, so a
+/// function can call its neighbours without knowing the namespace.
+pub(crate) fn qualify(stmts: &mut [YolkStmt], namespace: &str) {
+    let mut local = HashMap::new();
+    for stmt in stmts.iter() {
+        if let YolkStmt::Define { ident, .. } = stmt {
+            local.insert(ident.to_string(), format!("{}::{}", namespace, ident));
+        }
+    }
+    for stmt in stmts.iter_mut() {
+        if let YolkStmt::Define { ident, body, .. } = stmt {
+            *ident = format!("{}::{}", namespace, ident);
+            body.walk_mut(&mut |expr| {
+                if let YolkExpr::Call { ident, .. } = expr {
+                    if let Some(qualified) = local.get(ident) {
+                        *ident = qualified.to_string();
+                    }
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use crate::ast::{YolkExpr, YolkProgram, YolkStmt};
+use crate::error::YolkError;
+use crate::loader::Loader;
+
+use std::path::PathBuf;
+
+fn load(source: &str) -> (Loader, Result<YolkProgram, Vec<YolkError>>) {
+    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    path.push("tests/loader/main.yolk");
+    let mut loader = Loader::new();
+    let result = loader.load(&path, source);
+    (loader, result)
+}
+
+fn codes(result: Result<YolkProgram, Vec<YolkError>>) -> Vec<&'static str> {
+    let errors = result.expect_err("expected errors");
+    errors.iter().map(YolkError::code).collect()
+}
+
+#[test]
+fn test_load_use() -> Result<(), Vec<YolkError>> {
+    let (loader, result) = load("use \"lib.yolk\"\nlet a = lib::quadruple(1)");
+    let stmts: Vec<YolkStmt> = result?.into_iter().collect();
+    assert_eq!(loader.files().len(), 2);
+    match stmts.as_slice() {
+        [YolkStmt::Define {
+            ident: double,
+            span,
+            ..
+        }, YolkStmt::Define {
+            ident: quadruple,
+            body,
+            ..
+        }, YolkStmt::Let { .. }] => {
+            assert_eq!(double, "lib::double");
+            assert_eq!(quadruple, "lib::quadruple");
+            assert_eq!(span.file, 1);
+            match &**body {
+                YolkExpr::Call { ident, .. } => assert_eq!(ident, "lib::double"),
+                _ => panic!("expected call"),
+            }
+        }
+        _ => panic!("expected two definitions and an assignment"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_load_twice() -> Result<(), Vec<YolkError>> {
+    let (loader, result) = load("use \"lib.yolk\"\nuse \"./lib.yolk\"");
+    assert_eq!(result?.into_iter().count(), 2);
+    assert_eq!(loader.files().len(), 2);
+    Ok(())
+}
+
+#[test]
+fn test_load_cycle() {
+    let (_, result) = load("use \"cycle_a.yolk\"");
+    assert_eq!(codes(result), vec!["Y0023"]);
+}
+
+#[test]
+fn test_load_namespace_conflict() {
+    let (_, result) = load("use \"a/util.yolk\"\nuse \"b/util.yolk\"");
+    let errors = result.expect_err("expected errors");
+    match errors.as_slice() {
+        [YolkError::NamespaceConflict {
+            namespace,
+            span,
+            prev,
+            ..
+        }] => {
+            assert_eq!(namespace, "util");
+            assert_eq!((span.line, prev.line), (2, 1));
+        }
+        _ => panic!("expected namespace conflict"),
+    }
+}
+
+#[test]
+fn test_load_missing() {
+    let (_, result) = load("use \"missing.yolk\"");
+    assert_eq!(codes(result), vec!["Y0022"]);
+}
+
+#[test]
+fn test_load_errors_in_used_file() {
+    let (_, result) = load("use \"statement.yolk\"\nuse \"syntax.yolk\"");
+    let errors = result.expect_err("expected errors");
+    let found: Vec<(&str, usize)> = errors.iter().map(|e| (e.code(), e.span().file)).collect();
+    assert_eq!(found, vec![("Y0024", 1), ("Y0002", 2)]);
+}
@@ -0,0 +1,250 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::ast::{YolkProgram, YolkStmt, YololExpr, YololProgram, YololStmt};
+
+#[cfg(test)]
+mod tests;
+
+/// Yolol keywords, which cannot be used as variable names.
+const KEYWORDS: &[&str] = &[
+    "if", "then", "else", "end", "goto", "and", "or", "not", "abs", "sqrt", "sin", "cos", "tan",
+    "asin", "acos", "atan",
+];
+
+/// Maps the original names of renamed variables to their new names.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NameMap {
+    // Stores pairs of original and new names, in the order they were renamed
+    names: Vec<(String, String)>,
+}
+
+impl NameMap {
+    /// Gets the new name of a variable, if it was renamed.
+    pub fn get(&self, original: &str) -> Option<&str> {
+        self.names
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(original))
+            .map(|(_, renamed)| renamed.as_str())
+    }
+
+    /// Iterates over the original and new names of renamed variables.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.names
+            .iter()
+            .map(|(name, renamed)| (name.as_str(), renamed.as_str()))
+    }
+
+    /// Returns the number of renamed variables.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Returns whether no variables were renamed.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Records that a variable was renamed.
+    pub(crate) fn insert(&mut self, original: String, renamed: String) {
+        self.names.push((original, renamed));
+    }
+}
+
+impl fmt::Display for NameMap {
+    /// Formats a name map with one renamed variable per line.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let lines: Vec<String> = self
+            .names
+            .iter()
+            .map(|(name, renamed)| format!("{} -> {}", name, renamed))
+            .collect();
+        write!(f, "{}", lines.join("\n"))
+    }
+}
+
+/// Returns the Yolol names that a Yolk program shares with other Yolol code.
+///
+/// These are the names of imported variables and exported variables. If a
+/// program does not export any variables, every variable is assigned in Yolol,
+/// so the names of all top-level variables are external.
+pub fn external_names(program: &YolkProgram) -> HashSet<String> {
+    let mut names = HashSet::new();
+    let mut variables = HashSet::new();
+    let mut exports = false;
+    for stmt in program.clone().into_iter() {
+        match stmt {
+            YolkStmt::Import { ident, .. } => {
+                names.insert(ident);
+            }
+            YolkStmt::Let { ident, .. } => {
+                variables.insert(ident);
+            }
+            YolkStmt::Export { ident, alias, .. } => {
+                names.insert(alias.unwrap_or(ident));
+                exports = true;
+            }
+            _ => (),
+        }
+    }
+    if !exports {
+        names.extend(variables);
+    }
+    names
+}
+
+/// Renames the variables in a Yolol program to the shortest available names.
+///
+/// Variables named in `keep` are not renamed, and neither are the elements of
+/// kept arrays (e.g. `foo_0`) or data fields (e.g. `:foo`). The most frequently
+/// used variables get the shortest names.
+pub fn minify(program: YololProgram, keep: &HashSet<String>) -> (YololProgram, NameMap) {
+    let stmts: Vec<YololStmt> = program.into_iter().collect();
+    // Yolol identifiers are case-insensitive
+    let keep: HashSet<String> = keep.iter().map(|name| name.to_lowercase()).collect();
+    let is_kept = |ident: &str| {
+        let ident = ident.to_lowercase();
+        ident.starts_with(':')
+            || keep.contains(&ident)
+            || match ident.rfind('_') {
+                Some(i) => {
+                    let (array, index) = (&ident[..i], &ident[i + 1..]);
+                    !index.is_empty()
+                        && index.chars().all(|c| c.is_ascii_digit())
+                        && keep.contains(array)
+                }
+                None => false,
+            }
+    };
+
+    // Count uses in order of first appearance, so that ties are broken consistently
+    let mut order: Vec<String> = Vec::new();
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut reserved: HashSet<String> = keep.clone();
+    let mut count = |ident: &str| {
+        let key = ident.to_lowercase();
+        if is_kept(ident) {
+            reserved.insert(key);
+        } else {
+            let entry = counts.entry(key).or_insert(0);
+            if *entry == 0 {
+                order.push(ident.to_string());
+            }
+            *entry += 1;
+        }
+    };
+    for stmt in stmts.iter() {
+        match stmt {
+            YololStmt::Assign { ident, expr } => {
+                count(ident);
+                visit_idents(expr, &mut |ident| count(ident));
+            }
+        }
+    }
+    // Sorting is stable, so ties keep their order of first appearance
+    order.sort_by_key(|ident| std::cmp::Reverse(counts[&ident.to_lowercase()]));
+
+    let mut names = ShortNames::new(reserved);
+    let mut renamed: HashMap<String, String> = HashMap::new();
+    let mut map = NameMap::default();
+    for ident in order.into_iter() {
+        let name = names.next();
+        renamed.insert(ident.to_lowercase(), name.clone());
+        map.insert(ident, name);
+    }
+    let rename = |ident: String| match renamed.get(&ident.to_lowercase()) {
+        Some(name) => name.to_string(),
+        None => ident,
+    };
+    let stmts: Vec<YololStmt> = stmts
+        .into_iter()
+        .map(|stmt| match stmt {
+            YololStmt::Assign { ident, expr } => YololStmt::Assign {
+                ident: rename(ident),
+                expr: Box::new(rename_expr(*expr, &rename)),
+            },
+        })
+        .collect();
+    (stmts.into(), map)
+}
+
+/// Generates the shortest Yolol names that are not reserved.
+///
+/// Names start with a letter, followed by letters, digits or underscores.
+struct ShortNames {
+    // Stores the lowercase names that must not be generated
+    reserved: HashSet<String>,
+    next: usize,
+}
+
+impl ShortNames {
+    /// Creates a generator that avoids reserved names and Yolol keywords.
+    fn new(mut reserved: HashSet<String>) -> ShortNames {
+        reserved.extend(KEYWORDS.iter().map(|k| k.to_string()));
+        ShortNames { reserved, next: 0 }
+    }
+
+    /// Returns the next unused name.
+    fn next(&mut self) -> String {
+        loop {
+            let name = nth_name(self.next);
+            self.next += 1;
+            if self.reserved.insert(name.clone()) {
+                return name;
+            }
+        }
+    }
+}
+
+/// Returns the nth Yolol name, ordered by length.
+fn nth_name(mut n: usize) -> String {
+    const FIRST: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+    const REST: &[u8] = b"abcdefghijklmnopqrstuvwxyz0123456789_";
+    // Find the length of the name, then the index among names of that length
+    let mut len = 1;
+    let mut total = FIRST.len();
+    while n >= total {
+        n -= total;
+        len += 1;
+        total *= REST.len();
+    }
+    let mut tail = Vec::new();
+    for _ in 1..len {
+        tail.push(REST[n % REST.len()]);
+        n /= REST.len();
+    }
+    let mut name = vec![FIRST[n]];
+    name.extend(tail.into_iter().rev());
+    // Every byte is ASCII, so this cannot fail
+    String::from_utf8(name).unwrap()
+}
+
+/// Calls a function on every identifier in an expression.
+fn visit_idents(expr: &YololExpr, f: &mut dyn FnMut(&str)) {
+    match expr {
+        YololExpr::Prefix { expr, .. } => visit_idents(expr, f),
+        YololExpr::Infix { lhs, rhs, .. } => {
+            visit_idents(lhs, f);
+            visit_idents(rhs, f);
+        }
+        YololExpr::Ident(s) => f(s),
+        YololExpr::Literal(_) => (),
+    }
+}
+
+/// Renames every identifier in an expression.
+pub(crate) fn rename_expr(expr: YololExpr, rename: &dyn Fn(String) -> String) -> YololExpr {
+    match expr {
+        YololExpr::Prefix { op, expr } => YololExpr::Prefix {
+            op,
+            expr: Box::new(rename_expr(*expr, rename)),
+        },
+        YololExpr::Infix { lhs, op, rhs } => YololExpr::Infix {
+            lhs: Box::new(rename_expr(*lhs, rename)),
+            op,
+            rhs: Box::new(rename_expr(*rhs, rename)),
+        },
+        YololExpr::Ident(s) => YololExpr::Ident(rename(s)),
+        e => e,
+    }
+}
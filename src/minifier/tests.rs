@@ -0,0 +1,115 @@
+use crate::ast::{InfixOp, YolkProgram, YololExpr, YololProgram, YololStmt};
+use crate::error::YolkError;
+use crate::minifier::{external_names, minify, nth_name};
+
+use std::collections::HashSet;
+
+fn assign(ident: &str, expr: YololExpr) -> YololStmt {
+    YololStmt::Assign {
+        ident: ident.to_string(),
+        expr: Box::new(expr),
+    }
+}
+
+fn ident(s: &str) -> YololExpr {
+    YololExpr::Ident(s.to_string())
+}
+
+fn add(lhs: YololExpr, rhs: YololExpr) -> YololExpr {
+    YololExpr::Infix {
+        lhs: Box::new(lhs),
+        op: InfixOp::Add,
+        rhs: Box::new(rhs),
+    }
+}
+
+fn keep(names: &[&str]) -> HashSet<String> {
+    names.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_nth_name() {
+    assert_eq!(nth_name(0), "a");
+    assert_eq!(nth_name(25), "z");
+    assert_eq!(nth_name(26), "aa");
+    assert_eq!(nth_name(26 + 36), "a_");
+    assert_eq!(nth_name(26 + 37), "ba");
+    assert_eq!(nth_name(26 + 26 * 37), "aaa");
+}
+
+#[test]
+fn test_minify() {
+    let program: YololProgram = vec![
+        assign("velocity_0", ident("speed")),
+        assign("velocity_1", add(ident("velocity_0"), ident("speed"))),
+        assign("total", add(ident("velocity_0"), ident("velocity_1"))),
+    ]
+    .into();
+    let (minified, names) = minify(program, &keep(&["speed", "total"]));
+    assert_eq!(minified.to_string(), "a=speed b=a+speed total=a+b");
+    assert_eq!(names.get("velocity_0"), Some("a"));
+    assert_eq!(names.get("VELOCITY_1"), Some("b"));
+    assert_eq!(names.get("speed"), None);
+    assert_eq!(names.to_string(), "velocity_0 -> a\nvelocity_1 -> b");
+}
+
+#[test]
+fn test_minify_frequency() {
+    let program: YololProgram = vec![
+        assign("rare", ident("x")),
+        assign("common", add(ident("rare"), ident("x"))),
+        assign("out", add(ident("common"), ident("common"))),
+    ]
+    .into();
+    let (minified, _) = minify(program, &keep(&["x", "out"]));
+    assert_eq!(minified.to_string(), "b=x a=b+x out=a+a");
+}
+
+#[test]
+fn test_minify_reserved() {
+    // "A" and "b_0" are kept, and Yolol is case-insensitive
+    let program: YololProgram = vec![
+        assign("first", ident("A")),
+        assign("second", add(ident("first"), ident("b_0"))),
+        assign(":field", ident("second")),
+    ]
+    .into();
+    let (minified, _) = minify(program, &keep(&["a", "b"]));
+    assert_eq!(minified.to_string(), "c=A d=c+b_0 :field=d");
+}
+
+#[test]
+fn test_minify_keywords() {
+    let mut stmts = Vec::new();
+    for i in 0..26 * 37 {
+        stmts.push(assign(&format!("long_{}", i), ident("x")));
+    }
+    let (_, names) = minify(stmts.into(), &keep(&["x"]));
+    for (_, name) in names.iter() {
+        assert!(name != "if" && name != "or");
+        assert_ne!(name, "x");
+    }
+}
+
+#[test]
+fn test_external_names() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import foo
+        let bar = foo + 1
+        export bar
+        let baz = [bar, 2]
+        export baz as qux"
+        .parse()?;
+    assert_eq!(external_names(&yolk), keep(&["foo", "bar", "qux"]));
+    Ok(())
+}
+
+#[test]
+fn test_external_names_without_exports() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import foo
+        let bar = foo + 1
+        let baz = [bar, 2]
+        set bar = baz[1]"
+        .parse()?;
+    assert_eq!(external_names(&yolk), keep(&["foo", "bar", "baz"]));
+    Ok(())
+}
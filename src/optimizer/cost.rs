@@ -0,0 +1,131 @@
+use crate::ast::{InfixOp, PrefixOp, YololExpr, YololStmt};
+
+/// Returns the number of characters in a formatted Yolol expression.
+///
+/// This includes any parentheses and spaces that are needed when formatting.
+pub fn expr_cost(expr: &YololExpr) -> usize {
+    Layout::of(expr).cost()
+}
+
+/// Returns the number of characters in a formatted Yolol statement.
+pub fn stmt_cost(stmt: &YololStmt) -> usize {
+    match stmt {
+        YololStmt::Assign { ident, expr } => ident.len() + 1 + expr_cost(expr),
+    }
+}
+
+/// Returns the number of characters in formatted Yolol statements.
+///
+/// Statements are separated by a single space or newline.
+pub fn stmts_cost(stmts: &[YololStmt]) -> usize {
+    stmts.iter().map(stmt_cost).sum::<usize>() + stmts.len().saturating_sub(1)
+}
+
+/// Summarizes how a Yolol expression is formatted.
+///
+/// The layout of an expression only depends on the layouts of its operands,
+/// so costs can be computed bottom-up without formatting the expression.
+#[derive(Debug, Clone, Copy)]
+pub struct Layout {
+    // The number of characters, without surrounding parentheses
+    len: usize,
+    // Whether the expression starts with a minus sign, without surrounding parentheses
+    negative: bool,
+    // The precedence of the outermost operation, if there is one
+    prec: Option<u32>,
+    // Whether operations of equal precedence can contain the expression without parentheses
+    associative: bool,
+}
+
+impl Layout {
+    /// Computes the layout of an expression.
+    pub fn of(expr: &YololExpr) -> Layout {
+        match expr {
+            YololExpr::Prefix { op, expr } => Layout::prefix(*op, Layout::of(expr)),
+            YololExpr::Infix { lhs, op, rhs } => {
+                Layout::infix(Layout::of(lhs), *op, Layout::of(rhs))
+            }
+            YololExpr::Ident(s) => Layout::atom(s.len(), false),
+            YololExpr::Literal(y) => {
+                let s = y.to_string();
+                Layout::atom(s.len(), s.starts_with('-'))
+            }
+        }
+    }
+
+    fn atom(len: usize, negative: bool) -> Layout {
+        Layout {
+            len,
+            negative,
+            prec: None,
+            associative: true,
+        }
+    }
+
+    /// Computes the layout of a prefix operation from the layout of its operand.
+    pub fn prefix(op: PrefixOp, operand: Layout) -> Layout {
+        let prec = op.to_precedence();
+        let (mut len, mut wrapped, negative) = operand.wrap(prec);
+        // Consecutive minus signs would be parsed as a decrement
+        if op == PrefixOp::Neg && negative {
+            len += 2;
+            wrapped = true;
+        }
+        let op = op.as_str();
+        let spaced = !wrapped && is_alphabetic(op);
+        Layout {
+            len: op.len() + spaced as usize + len,
+            negative: op == "-",
+            prec: Some(prec),
+            associative: true,
+        }
+    }
+
+    /// Computes the layout of an infix operation from the layouts of its operands.
+    pub fn infix(lhs: Layout, op: InfixOp, rhs: Layout) -> Layout {
+        let prec = op.to_precedence();
+        let (lhs_len, lhs_wrapped, negative) = lhs.wrap(prec);
+        let rhs_prec = if op == InfixOp::Add { prec } else { prec + 1 };
+        let (mut rhs_len, mut rhs_wrapped, rhs_negative) = rhs.wrap(rhs_prec);
+        // Consecutive minus signs would be parsed as a decrement
+        if op == InfixOp::Sub && rhs_negative {
+            rhs_len += 2;
+            rhs_wrapped = true;
+        }
+        let associative = op.is_associative();
+        let op = op.as_str();
+        let alpha = is_alphabetic(op);
+        let spaces = (!lhs_wrapped && alpha) as usize + (!rhs_wrapped && alpha) as usize;
+        Layout {
+            len: lhs_len + op.len() + rhs_len + spaces,
+            negative,
+            prec: Some(prec),
+            associative,
+        }
+    }
+
+    /// Returns the number of characters in the formatted expression.
+    pub fn cost(&self) -> usize {
+        self.len
+    }
+
+    /// Returns the length, whether the expression is wrapped in parentheses,
+    /// and whether it starts with a minus sign, as an operand of the given precedence.
+    fn wrap(&self, parent_prec: u32) -> (usize, bool, bool) {
+        let wrapped = match self.prec {
+            Some(prec) if self.associative => prec < parent_prec,
+            Some(prec) => prec <= parent_prec,
+            None => false,
+        };
+        if wrapped {
+            (self.len + 2, true, false)
+        } else {
+            (self.len, false, self.negative)
+        }
+    }
+}
+
+/// Returns whether an operation must be surrounded with whitespace or parentheses.
+fn is_alphabetic(op: &str) -> bool {
+    op.chars().all(char::is_alphabetic)
+}
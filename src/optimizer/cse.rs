@@ -0,0 +1,217 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::ast::{InfixOp, PrefixOp, YololExpr, YololStmt};
+use crate::optimizer::cost::{expr_cost, stmts_cost};
+use crate::optimizer::{collect_idents, is_total_op};
+
+/// Hoists repeated subexpressions out of a statement into temporary variables.
+///
+/// A subexpression is only hoisted if the statements become shorter, and only
+/// if it can never cause a runtime error. A runtime error skips the rest of its
+/// line, and the temporary may end up on a different line than the statement.
+/// Temporary variables contain digits (e.g. `t0`), so they never conflict with
+/// Yolk variables.
+///
+/// Returns the temporary assignments, followed by the statement.
+pub fn hoist_stmt(stmt: YololStmt, temps: &mut Temporaries) -> Vec<YololStmt> {
+    let mut result = Vec::new();
+    hoist(stmt, temps, &mut result);
+    result
+}
+
+/// Generates names for temporary variables.
+pub struct Temporaries {
+    // Stores the lowercase identifiers that are already in use
+    used: HashSet<String>,
+    next: usize,
+}
+
+impl Temporaries {
+    /// Creates a generator that avoids the identifiers in statements.
+    pub fn new(stmts: &[YololStmt]) -> Temporaries {
+        let mut used = HashSet::new();
+        for stmt in stmts.iter() {
+            match stmt {
+                YololStmt::Assign { ident, expr } => {
+                    used.insert(ident.to_lowercase());
+                    collect_idents(expr, &mut used);
+                }
+            }
+        }
+        Temporaries { used, next: 0 }
+    }
+
+    /// Returns the next unused name without using it.
+    fn peek(&mut self) -> String {
+        while self.used.contains(&format!("t{}", self.next)) {
+            self.next += 1;
+        }
+        format!("t{}", self.next)
+    }
+
+    /// Returns the next unused name.
+    fn take(&mut self) -> String {
+        let name = self.peek();
+        self.used.insert(name.clone());
+        name
+    }
+}
+
+/// Hoists repeated subexpressions out of a statement until it cannot be shortened.
+fn hoist(stmt: YololStmt, temps: &mut Temporaries, result: &mut Vec<YololStmt>) {
+    match stmt {
+        YololStmt::Assign { ident, expr } => {
+            let name = temps.peek();
+            // Only the candidate with the largest estimated savings is formatted,
+            // since hoisting may also remove parentheses that are not estimated
+            let mut best: Option<(isize, &YololExpr)> = None;
+            for candidate in repeated_subexprs(&expr).into_iter().filter(|c| c.total) {
+                let savings = candidate.savings(name.len());
+                if best.map_or(true, |(s, _)| savings > s) {
+                    best = Some((savings, candidate.expr));
+                }
+            }
+            let hoisted = best.and_then(|(_, candidate)| {
+                let hoisted = vec![
+                    YololStmt::Assign {
+                        ident: name.clone(),
+                        expr: Box::new(candidate.clone()),
+                    },
+                    YololStmt::Assign {
+                        ident: ident.clone(),
+                        expr: Box::new(replace(&expr, candidate, &name)),
+                    },
+                ];
+                let cost = ident.len() + 1 + expr_cost(&expr);
+                if stmts_cost(&hoisted) < cost {
+                    Some(hoisted)
+                } else {
+                    None
+                }
+            });
+            match hoisted {
+                Some(hoisted) => {
+                    temps.take();
+                    for stmt in hoisted.into_iter() {
+                        hoist(stmt, temps, result);
+                    }
+                }
+                None => result.push(YololStmt::Assign { ident, expr }),
+            }
+        }
+    }
+}
+
+/// Represents a compound subexpression that occurs in an expression.
+struct Subexpr<'a> {
+    expr: &'a YololExpr,
+    // Estimates the formatted length, ignoring parentheses and spaces
+    size: usize,
+    count: usize,
+    total: bool,
+    // The position of the first occurrence in a preorder traversal
+    first: usize,
+}
+
+impl Subexpr<'_> {
+    /// Estimates the characters saved by hoisting the subexpression into a temporary.
+    fn savings(&self, name_len: usize) -> isize {
+        let replaced = self.count * self.size;
+        // The temporary assignment and its separator, and each use of it
+        let added = name_len + 1 + self.size + 1 + self.count * name_len;
+        replaced as isize - added as isize
+    }
+}
+
+/// Identifies an expression by its structure.
+///
+/// Operands are identified by the ids of their own keys, so equal expressions
+/// have equal keys without being compared node by node.
+#[derive(PartialEq, Eq, Hash)]
+enum Key<'a> {
+    Prefix(PrefixOp, usize),
+    Infix(usize, InfixOp, usize),
+    Ident(&'a str),
+    Literal(i128),
+}
+
+/// Finds the compound subexpressions that occur more than once in an expression.
+///
+/// Subexpressions are returned in the order that they are first found.
+fn repeated_subexprs(expr: &YololExpr) -> Vec<Subexpr> {
+    // Returns the id, estimated size, and totality of an expression
+    fn visit<'a>(
+        expr: &'a YololExpr,
+        ids: &mut HashMap<Key<'a>, usize>,
+        found: &mut Vec<Option<Subexpr<'a>>>,
+        visited: &mut usize,
+    ) -> (usize, usize, bool) {
+        let first = *visited;
+        *visited += 1;
+        let (key, size, total) = match expr {
+            YololExpr::Prefix { op, expr: operand } => {
+                let (id, size, total) = visit(operand, ids, found, visited);
+                let size = op.as_str().len() + size;
+                (Key::Prefix(*op, id), size, total && is_total_op(expr))
+            }
+            YololExpr::Infix { lhs, op, rhs } => {
+                let (lhs_id, lhs_size, lhs_total) = visit(lhs, ids, found, visited);
+                let (rhs_id, rhs_size, rhs_total) = visit(rhs, ids, found, visited);
+                let size = lhs_size + op.as_str().len() + rhs_size;
+                let total = lhs_total && rhs_total && is_total_op(expr);
+                (Key::Infix(lhs_id, *op, rhs_id), size, total)
+            }
+            YololExpr::Ident(ident) => (Key::Ident(ident), ident.len(), true),
+            YololExpr::Literal(y) => (Key::Literal(y.get_inner()), y.to_string().len(), true),
+        };
+        let compound = matches!(key, Key::Prefix(..) | Key::Infix(..));
+        let next = found.len();
+        let id = *ids.entry(key).or_insert(next);
+        if id == next {
+            found.push(if compound {
+                Some(Subexpr {
+                    expr,
+                    size,
+                    count: 0,
+                    total,
+                    first,
+                })
+            } else {
+                None
+            });
+        }
+        if let Some(subexpr) = &mut found[id] {
+            subexpr.count += 1;
+        }
+        (id, size, total)
+    }
+    let mut ids = HashMap::new();
+    let mut found = Vec::new();
+    visit(expr, &mut ids, &mut found, &mut 0);
+    let mut repeated: Vec<Subexpr> = found
+        .into_iter()
+        .flatten()
+        .filter(|subexpr| subexpr.count > 1)
+        .collect();
+    repeated.sort_by_key(|subexpr| subexpr.first);
+    repeated
+}
+
+/// Replaces every occurrence of a subexpression with a variable.
+fn replace(expr: &YololExpr, target: &YololExpr, ident: &str) -> YololExpr {
+    if expr == target {
+        return YololExpr::Ident(ident.to_string());
+    }
+    match expr {
+        YololExpr::Prefix { op, expr } => YololExpr::Prefix {
+            op: *op,
+            expr: Box::new(replace(expr, target, ident)),
+        },
+        YololExpr::Infix { lhs, op, rhs } => YololExpr::Infix {
+            lhs: Box::new(replace(lhs, target, ident)),
+            op: *op,
+            rhs: Box::new(replace(rhs, target, ident)),
+        },
+        e => e.clone(),
+    }
+}
@@ -0,0 +1,914 @@
+use std::collections::{HashMap, HashSet};
+
+use num_traits::identities::{One, Zero};
+use yolol_number::prelude::*;
+
+use crate::ast::{InfixOp, PrefixOp, YololExpr, YololProgram, YololStmt};
+use crate::optimizer::cost::Layout;
+use crate::optimizer::worklist::Worklist;
+
+mod cost;
+mod cse;
+mod worklist;
+
+pub use cost::{expr_cost, stmt_cost, stmts_cost};
+
+#[cfg(test)]
+mod tests;
+
+/// Optimizes a Yolol program.
+///
+/// The final value of every assigned variable is kept, so only assignments
+/// that are overwritten before they are read are removed.
+///
+/// This function is idempotent.
+pub fn optimize(program: YololProgram) -> YololProgram {
+    let roots = program
+        .clone()
+        .into_iter()
+        .map(|stmt| match stmt {
+            YololStmt::Assign { ident, .. } => ident,
+        })
+        .collect();
+    optimize_with_roots(program, &roots)
+}
+
+/// Optimizes a Yolol program, keeping the final values of the given roots.
+///
+/// Assignments that do not contribute to the value of a root are removed,
+/// variables that are only read once are inlined, and repeated subexpressions
+/// are hoisted into temporary variables. The elements of arrays are roots if
+/// the array is (e.g. `foo_0` if `foo` is a root), and data fields (e.g. `:foo`)
+/// are always roots, because other devices can read them.
+///
+/// A statement is only rewritten if the rewrite makes it shorter, so the
+/// result is never longer than the original program.
+///
+/// This function is idempotent.
+pub fn optimize_with_roots(program: YololProgram, roots: &HashSet<String>) -> YololProgram {
+    let stmts: Vec<YololStmt> = program.into_iter().collect();
+    let roots = expand_roots(&stmts, roots);
+    let mut worklist = Worklist::new(stmts);
+    // This loop will always terminate, because every change lowers the cost
+    // of the program, or keeps the cost and reads fewer variables
+    loop {
+        worklist.propagate();
+        worklist.eliminate_dead_code(&roots);
+        // Inlined statements may be reduced further, so they are propagated first
+        if worklist.inline_variables(&roots) {
+            continue;
+        }
+        // Hoisting only changes statements, so it is the last pass to make changes
+        if !worklist.eliminate_common_subexprs() {
+            break;
+        }
+    }
+    worklist.into_stmts().into()
+}
+
+/// Finds the lowercase identifiers of the roots, including the elements of arrays.
+fn expand_roots(stmts: &[YololStmt], roots: &HashSet<String>) -> HashSet<String> {
+    let mut expanded: HashSet<String> = roots.iter().map(|root| root.to_lowercase()).collect();
+    for stmt in stmts.iter() {
+        match stmt {
+            YololStmt::Assign { ident, .. } => {
+                let ident = ident.to_lowercase();
+                let is_element = match ident.rfind('_') {
+                    Some(i) => {
+                        let (array, index) = (&ident[..i], &ident[i + 1..]);
+                        !index.is_empty()
+                            && index.chars().all(|c| c.is_ascii_digit())
+                            && expanded.contains(array)
+                    }
+                    None => false,
+                };
+                if is_element {
+                    expanded.insert(ident);
+                }
+            }
+        }
+    }
+    expanded
+}
+
+/// Chooses the cheapest alternative to a statement.
+///
+/// Alternatives are ordered by cost, then by the number of variables that they
+/// read, so that equally long statements depend on fewer variables. The original
+/// statement is kept unless an alternative is strictly better.
+fn cheapest_stmt(stmt: YololStmt, vars: &HashMap<String, YololExpr>) -> YololStmt {
+    let key = |stmt: &YololStmt| match stmt {
+        YololStmt::Assign { expr, .. } => (stmt_cost(stmt), count_reads(expr)),
+    };
+    let reduced = reduce_stmt(stmt.clone(), vars);
+    let alternatives = vec![
+        rewrite_stmt(stmt.clone()),
+        rewrite_stmt(reduced.clone()),
+        reduced,
+    ];
+    let mut best = stmt;
+    let mut best_key = key(&best);
+    for alternative in alternatives.into_iter() {
+        let alternative_key = key(&alternative);
+        if alternative_key < best_key {
+            best = alternative;
+            best_key = alternative_key;
+        }
+    }
+    match best {
+        YololStmt::Assign { ident, expr } => YololStmt::Assign {
+            ident,
+            expr: Box::new(regroup_additions(*expr)),
+        },
+    }
+}
+
+/// Regroups additions to the left, e.g. "a+(b+c)" to "(a+b)+c".
+///
+/// Both are formatted as "a+b+c", so this keeps the tree the same as the
+/// formatted expression without changing its cost.
+fn regroup_additions(expr: YololExpr) -> YololExpr {
+    match expr {
+        YololExpr::Infix {
+            op: InfixOp::Add, ..
+        } => {
+            // Chains of additions are flattened, so each term is only regrouped once
+            let mut terms = Vec::new();
+            collect_terms(expr, &mut terms);
+            // A chain of additions always has at least two terms
+            let first = regroup_additions(terms.remove(0));
+            let terms = terms.into_iter().map(regroup_additions);
+            terms.fold(first, |lhs, rhs| YololExpr::Infix {
+                lhs: Box::new(lhs),
+                op: InfixOp::Add,
+                rhs: Box::new(rhs),
+            })
+        }
+        YololExpr::Prefix { op, expr } => YololExpr::Prefix {
+            op,
+            expr: Box::new(regroup_additions(*expr)),
+        },
+        YololExpr::Infix { lhs, op, rhs } => YololExpr::Infix {
+            lhs: Box::new(regroup_additions(*lhs)),
+            op,
+            rhs: Box::new(regroup_additions(*rhs)),
+        },
+        e => e,
+    }
+}
+
+/// Collects the terms of a chain of additions, in order.
+fn collect_terms(expr: YololExpr, terms: &mut Vec<YololExpr>) {
+    match expr {
+        YololExpr::Infix {
+            lhs,
+            op: InfixOp::Add,
+            rhs,
+        } => {
+            collect_terms(*lhs, terms);
+            collect_terms(*rhs, terms);
+        }
+        e => terms.push(e),
+    }
+}
+
+/// Counts the variables that are read by an expression.
+fn count_reads(expr: &YololExpr) -> usize {
+    match expr {
+        YololExpr::Prefix { expr, .. } => count_reads(expr),
+        YololExpr::Infix { lhs, rhs, .. } => count_reads(lhs) + count_reads(rhs),
+        YololExpr::Ident(_) => 1,
+        YololExpr::Literal(_) => 0,
+    }
+}
+
+/// Reduces a Yolol statement to a fixed point.
+fn reduce_stmt(stmt: YololStmt, vars: &HashMap<String, YololExpr>) -> YololStmt {
+    match stmt {
+        YololStmt::Assign { ident, expr } => {
+            let mut curr = *expr;
+            loop {
+                let next = reduce_expr(curr.clone(), vars);
+                if next == curr {
+                    break;
+                }
+                curr = next;
+            }
+            YololStmt::Assign {
+                ident,
+                expr: Box::new(curr),
+            }
+        }
+    }
+}
+
+/// Rewrites a Yolol statement into an equivalent form that is no longer.
+fn rewrite_stmt(stmt: YololStmt) -> YololStmt {
+    match stmt {
+        YololStmt::Assign { ident, expr } => YololStmt::Assign {
+            ident,
+            expr: Box::new(rewrite_expr(*expr).expr),
+        },
+    }
+}
+
+/// Represents a rewritten expression, with the layouts of it and its operands.
+struct Rewritten {
+    expr: YololExpr,
+    layout: Layout,
+    operands: Vec<Layout>,
+}
+
+/// Rewrites a Yolol expression into an equivalent form that is no longer.
+///
+/// Subexpressions are rewritten first, then the cheapest form of the
+/// expression itself is chosen. Layouts are computed bottom-up, so each form
+/// is costed without formatting it.
+fn rewrite_expr(expr: YololExpr) -> Rewritten {
+    match expr {
+        YololExpr::Prefix { op, expr } => {
+            let operand = rewrite_expr(*expr);
+            Rewritten {
+                expr: YololExpr::Prefix {
+                    op,
+                    expr: Box::new(operand.expr),
+                },
+                layout: Layout::prefix(op, operand.layout),
+                operands: vec![operand.layout],
+            }
+        }
+        YololExpr::Infix { lhs, op, rhs } => {
+            let (lhs, rhs) = (rewrite_expr(*lhs), rewrite_expr(*rhs));
+            let layout = Layout::infix(lhs.layout, op, rhs.layout);
+            match alternative(&lhs, op, &rhs) {
+                Some((alternative, alt_lhs, alt_op, alt_rhs))
+                    if Layout::infix(alt_lhs, alt_op, alt_rhs).cost() < layout.cost() =>
+                {
+                    Rewritten {
+                        expr: alternative.apply(lhs.expr, op, rhs.expr),
+                        layout: Layout::infix(alt_lhs, alt_op, alt_rhs),
+                        operands: vec![alt_lhs, alt_rhs],
+                    }
+                }
+                _ => Rewritten {
+                    expr: YololExpr::Infix {
+                        lhs: Box::new(lhs.expr),
+                        op,
+                        rhs: Box::new(rhs.expr),
+                    },
+                    layout,
+                    operands: vec![lhs.layout, rhs.layout],
+                },
+            }
+        }
+        e => Rewritten {
+            layout: Layout::of(&e),
+            expr: e,
+            operands: Vec::new(),
+        },
+    }
+}
+
+/// Represents an equivalent form of an infix expression.
+///
+/// Only the top of the expression is changed.
+#[derive(Debug, Clone, Copy)]
+enum Alternative {
+    // "a+-1" may be written as "a-1", and "a--1" as "a+1"
+    NegateLiteral,
+    // "a+-b" may be written as "a-b", and "a--b" as "a+b"
+    StripNeg,
+    // "2*(a+3)" may be written as "2*a+6"
+    Distribute,
+    // "-a+b" may be written as "b-a"
+    Commute,
+}
+
+impl Alternative {
+    /// Builds the alternative form of an infix expression from its operands.
+    ///
+    /// The operands are moved rather than cloned, so the cost of building an
+    /// alternative does not depend on the size of the expression.
+    fn apply(self, lhs: YololExpr, op: InfixOp, rhs: YololExpr) -> YololExpr {
+        let infix = |lhs: YololExpr, op: InfixOp, rhs: YololExpr| YololExpr::Infix {
+            lhs: Box::new(lhs),
+            op,
+            rhs: Box::new(rhs),
+        };
+        match (self, lhs, rhs) {
+            (Self::NegateLiteral, lhs, YololExpr::Literal(y)) => {
+                infix(lhs, negate_additive(op), YololExpr::Literal(-y))
+            }
+            (Self::StripNeg, lhs, YololExpr::Prefix { expr, .. }) => {
+                infix(lhs, negate_additive(op), *expr)
+            }
+            (Self::Distribute, YololExpr::Literal(c), YololExpr::Infix { lhs, op, rhs })
+            | (Self::Distribute, YololExpr::Infix { lhs, op, rhs }, YololExpr::Literal(c)) => {
+                let scale = |e: YololExpr| match e {
+                    YololExpr::Literal(y) => YololExpr::Literal(c.yolol_mul(y)),
+                    e => infix(YololExpr::Literal(c), InfixOp::Mul, e),
+                };
+                infix(scale(*lhs), op, scale(*rhs))
+            }
+            (Self::Commute, YololExpr::Prefix { expr, .. }, rhs) => infix(rhs, InfixOp::Sub, *expr),
+            (_, lhs, rhs) => infix(lhs, op, rhs),
+        }
+    }
+}
+
+/// Finds the equivalent form of an infix expression, if there is one.
+///
+/// Returns the form, and the layouts of its operands and its operation.
+fn alternative(
+    lhs: &Rewritten,
+    op: InfixOp,
+    rhs: &Rewritten,
+) -> Option<(Alternative, Layout, InfixOp, Layout)> {
+    match (&lhs.expr, op, &rhs.expr) {
+        // "x*x" must not be written as "x^2", because Yolol rounds the
+        // result of "^" but truncates the result of "*"
+        (_, InfixOp::Add, YololExpr::Literal(y)) | (_, InfixOp::Sub, YololExpr::Literal(y))
+            if y < &YololNumber::zero() && y > &YololNumber::min_value() =>
+        {
+            let negated = Layout::of(&YololExpr::Literal(-*y));
+            Some((
+                Alternative::NegateLiteral,
+                lhs.layout,
+                negate_additive(op),
+                negated,
+            ))
+        }
+        (
+            _,
+            InfixOp::Add,
+            YololExpr::Prefix {
+                op: PrefixOp::Neg, ..
+            },
+        )
+        | (
+            _,
+            InfixOp::Sub,
+            YololExpr::Prefix {
+                op: PrefixOp::Neg, ..
+            },
+        ) => Some((
+            Alternative::StripNeg,
+            lhs.layout,
+            negate_additive(op),
+            rhs.operands[0],
+        )),
+        (YololExpr::Literal(c), InfixOp::Mul, YololExpr::Infix { lhs: a, op, rhs: b })
+            if is_integer(c) && is_additive(*op) && (is_literal(a) || is_literal(b)) =>
+        {
+            let a = scaled_layout(c, a, rhs.operands[0]);
+            let b = scaled_layout(c, b, rhs.operands[1]);
+            Some((Alternative::Distribute, a, *op, b))
+        }
+        (YololExpr::Infix { lhs: a, op, rhs: b }, InfixOp::Mul, YololExpr::Literal(c))
+            if is_integer(c) && is_additive(*op) && (is_literal(a) || is_literal(b)) =>
+        {
+            let a = scaled_layout(c, a, lhs.operands[0]);
+            let b = scaled_layout(c, b, lhs.operands[1]);
+            Some((Alternative::Distribute, a, *op, b))
+        }
+        (
+            YololExpr::Prefix {
+                op: PrefixOp::Neg, ..
+            },
+            InfixOp::Add,
+            _,
+        ) => Some((
+            Alternative::Commute,
+            rhs.layout,
+            InfixOp::Sub,
+            lhs.operands[0],
+        )),
+        _ => None,
+    }
+}
+
+/// Returns the layout of an expression after it is multiplied by a constant.
+fn scaled_layout(c: &YololNumber, expr: &YololExpr, layout: Layout) -> Layout {
+    match expr {
+        YololExpr::Literal(y) => Layout::of(&YololExpr::Literal(c.yolol_mul(*y))),
+        _ => Layout::infix(Layout::of(&YololExpr::Literal(*c)), InfixOp::Mul, layout),
+    }
+}
+
+/// Swaps addition and subtraction.
+fn negate_additive(op: InfixOp) -> InfixOp {
+    match op {
+        InfixOp::Add => InfixOp::Sub,
+        _ => InfixOp::Add,
+    }
+}
+
+/// Folds a Yolol expression to a literal, if possible.
+///
+/// Variables are replaced with their literal values from `vars`, which is keyed
+/// by lowercase identifiers.
+pub fn fold_constant(expr: &YololExpr, vars: &HashMap<String, YololExpr>) -> Option<YololNumber> {
+    let mut curr = expr.clone();
+    loop {
+        let next = reduce_expr(curr.clone(), vars);
+        if next == curr {
+            break;
+        }
+        curr = next;
+    }
+    match curr {
+        YololExpr::Literal(y) => Some(y),
+        _ => None,
+    }
+}
+
+/// Finds the assignments that contribute to the value of a root.
+///
+/// Chips run their lines in a loop, so variables that are read before they are
+/// assigned are also live at the end of the program.
+fn live_stmts(stmts: &[YololStmt], roots: &HashSet<String>) -> Vec<bool> {
+    // Yolol identifiers are case-insensitive
+    let roots: HashSet<String> = roots.iter().map(|root| root.to_lowercase()).collect();
+    let mut live_out = roots.clone();
+    loop {
+        let (live, live_in) = sweep_dead_code(stmts, &live_out);
+        let next: HashSet<String> = roots.union(&live_in).cloned().collect();
+        // The live set only grows, so this loop will always terminate
+        if next == live_out {
+            return live;
+        }
+        live_out = next;
+    }
+}
+
+/// Sweeps backwards over statements, marking assignments to live variables.
+///
+/// Returns whether each statement is live, and the variables that are live before them.
+fn sweep_dead_code(
+    stmts: &[YololStmt],
+    live_out: &HashSet<String>,
+) -> (Vec<bool>, HashSet<String>) {
+    let mut live = live_out.clone();
+    let mut kept = vec![false; stmts.len()];
+    for (index, stmt) in stmts.iter().enumerate().rev() {
+        match stmt {
+            YololStmt::Assign { ident, expr } => {
+                let ident = ident.to_lowercase();
+                if live.remove(&ident) || ident.starts_with(':') {
+                    collect_idents(expr, &mut live);
+                    kept[index] = true;
+                }
+            }
+        }
+    }
+    (kept, live)
+}
+
+/// Collects the lowercase identifiers that are read by an expression.
+pub(crate) fn collect_idents(expr: &YololExpr, idents: &mut HashSet<String>) {
+    match expr {
+        YololExpr::Prefix { expr, .. } => collect_idents(expr, idents),
+        YololExpr::Infix { lhs, rhs, .. } => {
+            collect_idents(lhs, idents);
+            collect_idents(rhs, idents);
+        }
+        YololExpr::Ident(s) => {
+            idents.insert(s.to_lowercase());
+        }
+        YololExpr::Literal(_) => (),
+    }
+}
+
+/// Reduces a Yolol expression.
+///
+/// Operands are reduced first, then the expression itself is reduced until no
+/// reduction applies to it. Reductions only create new nodes at the top of an
+/// expression, so each node is visited a constant number of times.
+///
+/// This function will become idempotent after an optimum has been found.
+fn reduce_expr(expr: YololExpr, vars: &HashMap<String, YololExpr>) -> YololExpr {
+    let mut expr = match expr {
+        YololExpr::Prefix { op, expr } => YololExpr::Prefix {
+            op,
+            expr: Box::new(reduce_expr(*expr, vars)),
+        },
+        YololExpr::Infix { lhs, op, rhs } => YololExpr::Infix {
+            lhs: Box::new(reduce_expr(*lhs, vars)),
+            op,
+            rhs: Box::new(reduce_expr(*rhs, vars)),
+        },
+        // Propagate literal variables
+        YololExpr::Ident(s) => match vars.get(&s.to_lowercase()) {
+            Some(YololExpr::Literal(y)) => YololExpr::Literal(*y),
+            _ => YololExpr::Ident(s),
+        },
+        YololExpr::Literal(y) => YololExpr::Literal(y),
+    };
+    // This loop will always terminate, because every reduction removes nodes
+    loop {
+        match reduce_top(expr) {
+            Ok(reduced) => expr = reduced,
+            Err(unchanged) => return unchanged,
+        }
+    }
+}
+
+/// Applies a single reduction to the top of a Yolol expression.
+///
+/// Returns the original expression as an error if no reduction applies.
+fn reduce_top(expr: YololExpr) -> Result<YololExpr, YololExpr> {
+    let is_zero = |e: &YololExpr| matches!(e, YololExpr::Literal(y) if y.is_zero());
+    match expr {
+        YololExpr::Prefix { op, expr } => match (op, *expr) {
+            // Apply prefix operations literals
+            (op, YololExpr::Literal(y)) => match op {
+                PrefixOp::Neg if y > YololNumber::min_value() => Ok(YololExpr::Literal(-y)),
+                PrefixOp::Not => Ok(YololExpr::Literal(!y)),
+                PrefixOp::Abs if y > YololNumber::min_value() => Ok(YololExpr::Literal(y.abs())),
+                PrefixOp::Sqrt if y >= YololNumber::zero() => Ok(YololExpr::Literal(y.sqrt())),
+                PrefixOp::Sin => Ok(YololExpr::Literal(y.sin())),
+                PrefixOp::Cos => Ok(YololExpr::Literal(y.cos())),
+                PrefixOp::Tan
+                    if !y.cos().is_zero() && is_in_range(y.float_value().to_radians().tan()) =>
+                {
+                    Ok(YololExpr::Literal(y.tan()))
+                }
+                PrefixOp::Asin if y.abs() <= YololNumber::one() => Ok(YololExpr::Literal(y.asin())),
+                PrefixOp::Acos if y.abs() <= YololNumber::one() => Ok(YololExpr::Literal(y.acos())),
+                PrefixOp::Atan => Ok(YololExpr::Literal(y.atan())),
+                _ => Err(YololExpr::Prefix {
+                    op,
+                    expr: Box::new(YololExpr::Literal(y)),
+                }),
+            },
+            // Reduce double negation
+            (
+                PrefixOp::Neg,
+                YololExpr::Prefix {
+                    op: PrefixOp::Neg,
+                    expr: inner,
+                },
+            ) => Ok(*inner),
+            // Reduce "not not x" to "x" if x is already 0 or 1
+            (
+                PrefixOp::Not,
+                YololExpr::Prefix {
+                    op: PrefixOp::Not,
+                    expr: inner,
+                },
+            ) if is_boolean(&inner) => Ok(*inner),
+            // Reduce "-(a-b)" to "b-a"
+            (
+                PrefixOp::Neg,
+                YololExpr::Infix {
+                    lhs,
+                    op: InfixOp::Sub,
+                    rhs,
+                },
+            ) => Ok(YololExpr::Infix {
+                lhs: rhs,
+                op: InfixOp::Sub,
+                rhs: lhs,
+            }),
+            (op, expr) => Err(YololExpr::Prefix {
+                op,
+                expr: Box::new(expr),
+            }),
+        },
+        YololExpr::Infix { lhs, op, rhs } => match (*lhs, op, *rhs) {
+            // Reduce identity operations
+            (YololExpr::Literal(y), InfixOp::Add, rhs) if y.is_zero() => Ok(rhs),
+            (lhs, InfixOp::Add, YololExpr::Literal(y)) if y.is_zero() => Ok(lhs),
+            (lhs, InfixOp::Sub, YololExpr::Literal(y)) if y.is_zero() => Ok(lhs),
+            // An operand can only be dropped if it cannot cause a runtime error,
+            // because a runtime error skips the rest of the line
+            (lhs, InfixOp::Mul, rhs)
+                if (is_zero(&lhs) || is_zero(&rhs)) && is_total(&lhs) && is_total(&rhs) =>
+            {
+                Ok(YololExpr::Literal(YololNumber::zero()))
+            }
+            (lhs, InfixOp::Mul, YololExpr::Literal(y)) if y.is_one() => Ok(lhs),
+            (YololExpr::Literal(y), InfixOp::Mul, rhs) if y.is_one() => Ok(rhs),
+
+            (lhs, InfixOp::Div, YololExpr::Literal(y)) if y.is_one() => Ok(lhs),
+            (YololExpr::Literal(y), InfixOp::Exp, rhs) if y.is_one() && is_total(&rhs) => {
+                Ok(YololExpr::Literal(YololNumber::one()))
+            }
+            (lhs, InfixOp::Exp, YololExpr::Literal(y)) if y.is_one() => Ok(lhs),
+            // Reduce "x-x" to 0, and "x/x" to 1 if x cannot be 0
+            (x, InfixOp::Sub, y) if x == y && is_total(&x) => {
+                Ok(YololExpr::Literal(YololNumber::zero()))
+            }
+            (x, InfixOp::Div, y) if x == y && is_total(&x) && is_nonzero(&x) => {
+                Ok(YololExpr::Literal(YololNumber::one()))
+            }
+            // Reduce "not not x" to "x" in boolean context
+            (
+                YololExpr::Prefix {
+                    op: PrefixOp::Not,
+                    expr: inner,
+                },
+                InfixOp::And,
+                rhs,
+            )
+            | (
+                YololExpr::Prefix {
+                    op: PrefixOp::Not,
+                    expr: inner,
+                },
+                InfixOp::Or,
+                rhs,
+            ) if is_not(&inner) => Ok(YololExpr::Infix {
+                lhs: Box::new(strip_not(&inner)),
+                op,
+                rhs: Box::new(rhs),
+            }),
+            (
+                lhs,
+                InfixOp::And,
+                YololExpr::Prefix {
+                    op: PrefixOp::Not,
+                    expr: inner,
+                },
+            )
+            | (
+                lhs,
+                InfixOp::Or,
+                YololExpr::Prefix {
+                    op: PrefixOp::Not,
+                    expr: inner,
+                },
+            ) if is_not(&inner) => Ok(YololExpr::Infix {
+                lhs: Box::new(lhs),
+                op,
+                rhs: Box::new(strip_not(&inner)),
+            }),
+            // Reassociate constants, e.g. "(a+1)+2" to "a+3"
+            (
+                YololExpr::Infix {
+                    lhs: x,
+                    op: inner,
+                    rhs: c,
+                },
+                outer,
+                YololExpr::Literal(d),
+            ) if is_additive(inner) && is_additive(outer) && is_literal(&c) => {
+                let c = literal_value(&c);
+                // "x+c+d", "x+c-d", "x-c+d" and "x-c-d" all add a constant to x
+                let c = if inner == InfixOp::Sub {
+                    YololNumber::zero().yolol_sub(c)
+                } else {
+                    c
+                };
+                let sum = match outer {
+                    InfixOp::Add => c.yolol_add(d),
+                    _ => c.yolol_sub(d),
+                };
+                Ok(add_constant(*x, sum))
+            }
+            (
+                YololExpr::Infix {
+                    lhs: c,
+                    op: InfixOp::Add,
+                    rhs: x,
+                },
+                outer,
+                YololExpr::Literal(d),
+            ) if is_additive(outer) && is_literal(&c) => {
+                let c = literal_value(&c);
+                let sum = match outer {
+                    InfixOp::Add => c.yolol_add(d),
+                    _ => c.yolol_sub(d),
+                };
+                Ok(add_constant(*x, sum))
+            }
+            // Reassociate integer factors, e.g. "(a*2)*3" to "a*6"
+            (
+                YololExpr::Infix {
+                    lhs: x,
+                    op: InfixOp::Mul,
+                    rhs: c,
+                },
+                InfixOp::Mul,
+                YololExpr::Literal(d),
+            )
+            | (
+                YololExpr::Infix {
+                    lhs: c,
+                    op: InfixOp::Mul,
+                    rhs: x,
+                },
+                InfixOp::Mul,
+                YololExpr::Literal(d),
+            ) if is_integer_literal(&c) && is_integer(&d) => Ok(YololExpr::Infix {
+                lhs: x,
+                op: InfixOp::Mul,
+                rhs: Box::new(YololExpr::Literal(literal_value(&c).yolol_mul(d))),
+            }),
+            // Apply infix operations to literals
+            (YololExpr::Literal(y), op, YololExpr::Literal(z)) => match op {
+                InfixOp::Add => Ok(YololExpr::Literal(y.yolol_add(z))),
+                InfixOp::Sub => Ok(YololExpr::Literal(y.yolol_sub(z))),
+                InfixOp::Mul => Ok(YololExpr::Literal(y.yolol_mul(z))),
+                // Unwrap cannot panic because z cannot be zero
+                InfixOp::Div if !z.is_zero() => Ok(YololExpr::Literal(y.yolol_div(z).unwrap())),
+                InfixOp::Mod if !z.is_zero() => Ok(YololExpr::Literal(y.yolol_mod(z))),
+                InfixOp::Exp
+                    if (!y.is_zero() || !z.is_zero())
+                        && is_in_range(y.float_value().powf(z.float_value())) =>
+                {
+                    Ok(YololExpr::Literal(y.pow(z)))
+                }
+                InfixOp::LessThan => Ok(YololExpr::Literal((y < z).into())),
+                InfixOp::LessEqual => Ok(YololExpr::Literal((y <= z).into())),
+                InfixOp::GreaterThan => Ok(YololExpr::Literal((y > z).into())),
+                InfixOp::GreaterEqual => Ok(YololExpr::Literal((y >= z).into())),
+                InfixOp::Equal => Ok(YololExpr::Literal((y == z).into())),
+                InfixOp::NotEqual => Ok(YololExpr::Literal((y != z).into())),
+                InfixOp::And => Ok(YololExpr::Literal((!y.is_zero() && !z.is_zero()).into())),
+                InfixOp::Or => Ok(YololExpr::Literal((!y.is_zero() || !z.is_zero()).into())),
+                _ => Err(YololExpr::Infix {
+                    lhs: Box::new(YololExpr::Literal(y)),
+                    op,
+                    rhs: Box::new(YololExpr::Literal(z)),
+                }),
+            },
+            (lhs, op, rhs) => Err(YololExpr::Infix {
+                lhs: Box::new(lhs),
+                op,
+                rhs: Box::new(rhs),
+            }),
+        },
+        e => Err(e),
+    }
+}
+
+/// Returns whether evaluating an expression can never cause a runtime error.
+///
+/// Division and modulo by zero, and square roots or inverse trigonometric
+/// functions that are out of range, cause runtime errors. Exponents of
+/// variables may also be out of range (e.g. `a^0.5` for a negative `a`).
+pub(crate) fn is_total(expr: &YololExpr) -> bool {
+    is_total_op(expr)
+        && match expr {
+            YololExpr::Prefix { expr, .. } => is_total(expr),
+            YololExpr::Infix { lhs, rhs, .. } => is_total(lhs) && is_total(rhs),
+            YololExpr::Ident(_) | YololExpr::Literal(_) => true,
+        }
+}
+
+/// Returns whether the outermost operation of an expression can never cause a
+/// runtime error, assuming that its operands never do.
+pub(crate) fn is_total_op(expr: &YololExpr) -> bool {
+    match expr {
+        YololExpr::Prefix { op, .. } => !matches!(
+            op,
+            PrefixOp::Sqrt | PrefixOp::Tan | PrefixOp::Asin | PrefixOp::Acos
+        ),
+        YololExpr::Infix { lhs, op, rhs } => match op {
+            InfixOp::Div | InfixOp::Mod => {
+                matches!(&**rhs, YololExpr::Literal(y) if !y.is_zero())
+            }
+            InfixOp::Exp => matches!(
+                (&**lhs, &**rhs),
+                (YololExpr::Literal(_), YololExpr::Literal(_))
+            ),
+            _ => true,
+        },
+        YololExpr::Ident(_) | YololExpr::Literal(_) => true,
+    }
+}
+
+/// Returns whether an expression always evaluates to 0 or 1.
+fn is_boolean(expr: &YololExpr) -> bool {
+    match expr {
+        YololExpr::Prefix { op, .. } => *op == PrefixOp::Not,
+        YololExpr::Infix { op, .. } => matches!(
+            op,
+            InfixOp::LessThan
+                | InfixOp::LessEqual
+                | InfixOp::GreaterThan
+                | InfixOp::GreaterEqual
+                | InfixOp::Equal
+                | InfixOp::NotEqual
+                | InfixOp::And
+                | InfixOp::Or
+        ),
+        YololExpr::Literal(y) => y.is_zero() || y.is_one(),
+        YololExpr::Ident(_) => false,
+    }
+}
+
+/// Returns whether an expression can never evaluate to 0.
+fn is_nonzero(expr: &YololExpr) -> bool {
+    match expr {
+        YololExpr::Prefix {
+            op: PrefixOp::Neg,
+            expr,
+        } => is_nonzero(expr),
+        YololExpr::Literal(y) => !y.is_zero(),
+        e => is_positive(e),
+    }
+}
+
+/// Returns whether an expression always evaluates to a positive number.
+///
+/// Addition saturates, so the sum of a positive number and a non-negative number is positive.
+fn is_positive(expr: &YololExpr) -> bool {
+    match expr {
+        YololExpr::Literal(y) => y > &YololNumber::zero(),
+        YololExpr::Infix {
+            lhs,
+            op: InfixOp::Add,
+            rhs,
+        } => {
+            (is_positive(lhs) && is_non_negative(rhs)) || (is_non_negative(lhs) && is_positive(rhs))
+        }
+        _ => false,
+    }
+}
+
+/// Returns whether an expression always evaluates to a non-negative number.
+fn is_non_negative(expr: &YololExpr) -> bool {
+    match expr {
+        YololExpr::Prefix {
+            op: PrefixOp::Abs, ..
+        } => true,
+        YololExpr::Literal(y) => y >= &YololNumber::zero(),
+        e => is_positive(e),
+    }
+}
+
+/// Returns whether an expression is a `not` operation.
+fn is_not(expr: &YololExpr) -> bool {
+    matches!(
+        expr,
+        YololExpr::Prefix {
+            op: PrefixOp::Not,
+            ..
+        }
+    )
+}
+
+/// Removes `not` from an expression that is known to be a `not` operation.
+fn strip_not(expr: &YololExpr) -> YololExpr {
+    match expr {
+        YololExpr::Prefix {
+            op: PrefixOp::Not,
+            expr,
+        } => *expr.clone(),
+        e => e.clone(),
+    }
+}
+
+/// Returns whether an operation adds or subtracts.
+fn is_additive(op: InfixOp) -> bool {
+    op == InfixOp::Add || op == InfixOp::Sub
+}
+
+/// Returns whether an expression is a literal.
+fn is_literal(expr: &YololExpr) -> bool {
+    matches!(expr, YololExpr::Literal(_))
+}
+
+/// Returns whether an expression is a literal integer.
+fn is_integer_literal(expr: &YololExpr) -> bool {
+    matches!(expr, YololExpr::Literal(y) if is_integer(y))
+}
+
+/// Returns whether a Yolol number is an integer.
+///
+/// Multiplying by an integer never truncates, so integer factors can be reassociated.
+fn is_integer(y: &YololNumber) -> bool {
+    y.get_inner() % YololNumber::one().get_inner() == 0
+}
+
+/// Gets the value of an expression that is known to be a literal.
+fn literal_value(expr: &YololExpr) -> YololNumber {
+    match expr {
+        YololExpr::Literal(y) => *y,
+        _ => YololNumber::zero(),
+    }
+}
+
+/// Adds a constant to an expression, subtracting if the constant is negative.
+fn add_constant(expr: YololExpr, c: YololNumber) -> YololExpr {
+    if c < YololNumber::zero() && c > YololNumber::min_value() {
+        YololExpr::Infix {
+            lhs: Box::new(expr),
+            op: InfixOp::Sub,
+            rhs: Box::new(YololExpr::Literal(-c)),
+        }
+    } else {
+        YololExpr::Infix {
+            lhs: Box::new(expr),
+            op: InfixOp::Add,
+            rhs: Box::new(YololExpr::Literal(c)),
+        }
+    }
+}
+
+/// Checks if a float can be converted to a Yolol number without overflowing.
+fn is_in_range(f: f64) -> bool {
+    f.is_finite() && f.abs() < 922_337_203_685_477.0
+}
@@ -0,0 +1,429 @@
+use yolol_number::YololNumber;
+
+use crate::ast::{InfixOp, PrefixOp, YolkProgram, YololExpr, YololProgram, YololStmt};
+use crate::error::YolkError;
+use crate::optimizer::{expr_cost, optimize, optimize_with_roots, stmt_cost, stmts_cost};
+
+use std::collections::HashSet;
+use std::convert::TryFrom;
+
+fn assign(ident: &str, expr: YololExpr) -> YololStmt {
+    YololStmt::Assign {
+        ident: ident.to_string(),
+        expr: Box::new(expr),
+    }
+}
+
+fn ident(s: &str) -> YololExpr {
+    YololExpr::Ident(s.to_string())
+}
+
+fn literal(n: i64) -> YololExpr {
+    YololExpr::Literal(YololNumber::from_value(n))
+}
+
+fn add(lhs: YololExpr, rhs: YololExpr) -> YololExpr {
+    YololExpr::Infix {
+        lhs: Box::new(lhs),
+        op: InfixOp::Add,
+        rhs: Box::new(rhs),
+    }
+}
+
+fn roots(idents: &[&str]) -> HashSet<String> {
+    idents.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_overwritten() {
+    let program: YololProgram = vec![
+        assign("x", add(ident("a"), literal(1))),
+        assign("x", add(ident("b"), literal(2))),
+    ]
+    .into();
+    let expected: YololProgram = vec![assign("x", add(ident("b"), literal(2)))].into();
+    assert_eq!(optimize(program), expected);
+}
+
+#[test]
+fn test_roots() {
+    let program: YololProgram = vec![
+        assign("x", literal(1)),
+        assign("y", add(ident("a"), ident("x"))),
+        assign("z", add(ident("y"), ident("b"))),
+    ]
+    .into();
+    let expected: YololProgram =
+        vec![assign("z", add(add(ident("a"), literal(1)), ident("b")))].into();
+    assert_eq!(
+        optimize_with_roots(program.clone(), &roots(&["z"])),
+        expected
+    );
+    assert_eq!(optimize_with_roots(program, &roots(&[])), Vec::new().into());
+}
+
+#[test]
+fn test_roots_case_insensitive() {
+    let program: YololProgram = vec![assign("Foo", ident("a"))].into();
+    assert_eq!(
+        optimize_with_roots(program.clone(), &roots(&["fOO"])),
+        program
+    );
+}
+
+#[test]
+fn test_propagation_case_insensitive() {
+    let program: YololProgram = vec![
+        assign("A", literal(1)),
+        assign("b", add(ident("a"), literal(1))),
+        assign("a", ident("x")),
+        assign("c", add(ident("A"), literal(1))),
+    ]
+    .into();
+    let expected: YololProgram = vec![
+        assign("b", literal(2)),
+        assign("a", ident("x")),
+        assign("c", add(ident("A"), literal(1))),
+    ]
+    .into();
+    assert_eq!(
+        optimize_with_roots(program, &roots(&["a", "b", "c"])),
+        expected
+    );
+}
+
+#[test]
+fn test_data_fields() {
+    let program: YololProgram = vec![
+        assign("x", ident("a")),
+        assign(":out", add(ident("x"), ident("b"))),
+    ]
+    .into();
+    let expected: YololProgram = vec![assign(":out", add(ident("a"), ident("b")))].into();
+    assert_eq!(optimize_with_roots(program, &roots(&[])), expected);
+}
+
+#[test]
+fn test_read_before_assign() {
+    // The chip loops, so `count` is read before it is assigned on every pass
+    let program: YololProgram = vec![
+        assign(":out", ident("count")),
+        assign("count", add(ident("count"), literal(1))),
+        assign("unused", ident("count")),
+    ]
+    .into();
+    let expected: YololProgram = vec![
+        assign(":out", ident("count")),
+        assign("count", add(ident("count"), literal(1))),
+    ]
+    .into();
+    assert_eq!(optimize_with_roots(program, &roots(&[])), expected);
+}
+
+#[test]
+fn test_idempotence() {
+    let program: YololProgram = vec![
+        assign("x", literal(1)),
+        assign("y", add(ident("x"), ident("a"))),
+        assign("x", add(ident("y"), ident("y"))),
+        assign(":out", ident("x")),
+    ]
+    .into();
+    let once = optimize_with_roots(program, &roots(&["y"]));
+    let twice = optimize_with_roots(once.clone(), &roots(&["y"]));
+    assert_eq!(once, twice);
+}
+
+fn mul(lhs: YololExpr, rhs: YololExpr) -> YololExpr {
+    YololExpr::Infix {
+        lhs: Box::new(lhs),
+        op: InfixOp::Mul,
+        rhs: Box::new(rhs),
+    }
+}
+
+fn infix(lhs: YololExpr, op: InfixOp, rhs: YololExpr) -> YololExpr {
+    YololExpr::Infix {
+        lhs: Box::new(lhs),
+        op,
+        rhs: Box::new(rhs),
+    }
+}
+
+#[test]
+fn test_common_subexprs() {
+    // "x=(a*b+c*d)*(a*b+c*d+1)" is longer than "t0=a*b+c*d x=t0*(t0+1)"
+    let big = add(mul(ident("a"), ident("b")), mul(ident("c"), ident("d")));
+    let program: YololProgram =
+        vec![assign("x", mul(big.clone(), add(big.clone(), literal(1))))].into();
+    let expected: YololProgram = vec![
+        assign("t0", big),
+        assign("x", mul(ident("t0"), add(ident("t0"), literal(1)))),
+    ]
+    .into();
+    assert_eq!(optimize(program), expected);
+}
+
+#[test]
+fn test_common_subexprs_longer() {
+    // "x=(a+b)*(a+b+1)" is shorter than "t0=a+b x=t0*(t0+1)"
+    let small = add(ident("a"), ident("b"));
+    let program: YololProgram =
+        vec![assign("x", mul(small.clone(), add(small, literal(1))))].into();
+    assert_eq!(optimize(program.clone()), program);
+}
+
+#[test]
+fn test_common_subexprs_runtime_errors() {
+    // "t0=a/b+c/d" could be laid out on a different line than "x", which would
+    // then read a stale value if the division failed
+    let big = add(
+        infix(ident("a"), InfixOp::Div, ident("b")),
+        infix(ident("c"), InfixOp::Div, ident("d")),
+    );
+    let program: YololProgram = vec![assign("x", mul(big.clone(), add(big, literal(1))))].into();
+    assert_eq!(optimize(program.clone()), program);
+}
+
+#[test]
+fn test_common_subexprs_unused_name() {
+    let big = add(mul(ident("a"), ident("b")), mul(ident("c"), ident("t0")));
+    let program: YololProgram = vec![
+        assign("T1", ident("a")),
+        assign("x", mul(big.clone(), add(big.clone(), literal(1)))),
+    ]
+    .into();
+    let expected: YololProgram = vec![
+        assign("T1", ident("a")),
+        assign("t2", big),
+        assign("x", mul(ident("t2"), add(ident("t2"), literal(1)))),
+    ]
+    .into();
+    assert_eq!(optimize(program), expected);
+}
+
+#[test]
+fn test_common_subexprs_many() {
+    let big = add(mul(ident("a"), ident("b")), mul(ident("c"), ident("d")));
+    let compare =
+        |e: YololExpr, i: usize| infix(e, InfixOp::GreaterThan, ident(&format!("v{}", i)));
+    let mut expr = compare(big.clone(), 0);
+    let mut hoisted = compare(ident("t0"), 0);
+    for i in 1..500 {
+        expr = add(expr, compare(big.clone(), i));
+        hoisted = add(hoisted, compare(ident("t0"), i));
+    }
+    let program: YololProgram = vec![assign("x", expr)].into();
+    let expected: YololProgram = vec![assign("t0", big), assign("x", hoisted)].into();
+    assert_eq!(optimize(program), expected);
+}
+
+#[test]
+fn test_square() {
+    // "(a+b)^2" is shorter, but it may round differently
+    let big = add(ident("a"), ident("b"));
+    let program: YololProgram = vec![assign("x", mul(big.clone(), big))].into();
+    assert_eq!(optimize(program.clone()), program);
+}
+
+#[test]
+fn test_negation() {
+    let program: YololProgram = vec![
+        assign("x", add(ident("a"), literal(-1))),
+        assign("y", infix(ident("a"), InfixOp::Sub, literal(-2))),
+        assign(
+            "z",
+            add(
+                YololExpr::Prefix {
+                    op: PrefixOp::Neg,
+                    expr: Box::new(ident("a")),
+                },
+                ident("b"),
+            ),
+        ),
+    ]
+    .into();
+    assert_eq!(optimize(program).to_string(), "x=a-1 y=a+2 z=b-a");
+}
+
+#[test]
+fn test_longer_literal() {
+    // "x=1/3" is shorter than "x=0.333", but the value is still propagated
+    let program: YololProgram = vec![
+        assign("x", infix(literal(1), InfixOp::Div, literal(3))),
+        assign("y", mul(ident("x"), literal(3))),
+        assign("z", infix(ident("x"), InfixOp::LessThan, literal(1))),
+    ]
+    .into();
+    assert_eq!(optimize(program).to_string(), "x=1/3 y=x*3 z=1");
+}
+
+#[test]
+fn test_cost() {
+    let stmts = vec![
+        assign("x", mul(add(ident("a"), ident("b")), ident("c"))),
+        assign(
+            "y",
+            YololExpr::Prefix {
+                op: PrefixOp::Not,
+                expr: Box::new(ident("x")),
+            },
+        ),
+    ];
+    assert_eq!(expr_cost(&mul(add(ident("a"), ident("b")), ident("c"))), 7);
+    assert_eq!(stmt_cost(&stmts[0]), 9);
+    assert_eq!(stmt_cost(&stmts[1]), 7);
+    assert_eq!(stmts_cost(&stmts), 17);
+    assert_eq!(stmts_cost(&[]), 0);
+}
+
+#[test]
+fn test_cost_matches_format() {
+    let neg = |e: YololExpr| YololExpr::Prefix {
+        op: PrefixOp::Neg,
+        expr: Box::new(e),
+    };
+    let not = |e: YololExpr| YololExpr::Prefix {
+        op: PrefixOp::Not,
+        expr: Box::new(e),
+    };
+    let exprs = vec![
+        infix(ident("a"), InfixOp::Sub, neg(ident("b"))),
+        infix(ident("a"), InfixOp::Sub, literal(-1)),
+        neg(neg(ident("a"))),
+        neg(literal(-1)),
+        not(add(ident("a"), ident("b"))),
+        not(not(ident("a"))),
+        infix(
+            infix(ident("a"), InfixOp::And, ident("b")),
+            InfixOp::Or,
+            not(ident("c")),
+        ),
+        infix(
+            ident("a"),
+            InfixOp::And,
+            infix(ident("b"), InfixOp::And, ident("c")),
+        ),
+        infix(ident("a"), InfixOp::Sub, add(ident("b"), ident("c"))),
+        add(ident("a"), add(ident("b"), ident("c"))),
+        mul(ident("a"), mul(ident("b"), ident("c"))),
+        mul(neg(ident("a")), infix(ident("b"), InfixOp::Exp, literal(2))),
+        add(neg(add(ident("a"), ident("b"))), literal(-3)),
+    ];
+    for expr in exprs.iter() {
+        assert_eq!(expr_cost(expr), expr.to_string().len(), "{}", expr);
+    }
+}
+
+/// Transpiles and optimizes an expression of the imported variables `a` and `b`.
+fn optimize_expr(expr: &str) -> Result<String, Vec<YolkError>> {
+    let yolk: YolkProgram = format!("import a\nimport b\nlet n = {}", expr).parse()?;
+    Ok(YololProgram::try_from(yolk)?.optimize().to_string())
+}
+
+#[test]
+fn test_reassociate() -> Result<(), Vec<YolkError>> {
+    assert_eq!(optimize_expr("(a + 1) + 2")?, "n=a+3");
+    assert_eq!(optimize_expr("(a - 1.5) + 2")?, "n=a+0.5");
+    assert_eq!(optimize_expr("(1 + a) - 3")?, "n=a-2");
+    assert_eq!(optimize_expr("(a - 1) - 2")?, "n=a-3");
+    assert_eq!(optimize_expr("(a + 0.5) - 0.5")?, "n=a");
+    assert_eq!(optimize_expr("(a * 2) * 3")?, "n=a*6");
+    // Multiplying by 0.5 truncates, so the factors cannot be combined
+    assert_eq!(optimize_expr("(a * 0.5) * 0.5")?, "n=a*0.5*0.5");
+    Ok(())
+}
+
+#[test]
+fn test_cancel() -> Result<(), Vec<YolkError>> {
+    assert_eq!(optimize_expr("a - a")?, "n=0");
+    assert_eq!(optimize_expr("(a + b) - (a + b)")?, "n=0");
+    assert_eq!(optimize_expr("(abs(a) + 1) / (abs(a) + 1)")?, "n=1");
+    // Division by zero is a runtime error, which must be kept
+    assert_eq!(optimize_expr("a / a")?, "n=a/a");
+    assert_eq!(optimize_expr("1 / a - 1 / a")?, "n=1/a-1/a");
+    assert_eq!(optimize_expr("(1 / a) * 0")?, "n=(1/a)*0");
+    assert_eq!(optimize_expr("1 ^ (1 / a)")?, "n=1^(1/a)");
+    assert_eq!(optimize_expr("a * 0")?, "n=0");
+    assert_eq!(optimize_expr("1 ^ a")?, "n=1");
+    Ok(())
+}
+
+#[test]
+fn test_exponent_one() -> Result<(), Vec<YolkError>> {
+    assert_eq!(optimize_expr("a ^ 1")?, "n=a");
+    assert_eq!(optimize_expr("(a + b) ^ 1")?, "n=a+b");
+    Ok(())
+}
+
+#[test]
+fn test_negate() -> Result<(), Vec<YolkError>> {
+    assert_eq!(optimize_expr("--a")?, "n=a");
+    assert_eq!(optimize_expr("-(a - b)")?, "n=b-a");
+    assert_eq!(optimize_expr("not(not(a < b))")?, "n=a<b");
+    // "not not a" is 0 or 1, but "a" may be any number
+    assert_eq!(optimize_expr("not(not(a))")?, "n=not not a");
+    assert_eq!(optimize_expr("not(not(a)) and b")?, "n=a and b");
+    assert_eq!(optimize_expr("a or not(not(b))")?, "n=a or b");
+    Ok(())
+}
+
+#[test]
+fn test_distribute() -> Result<(), Vec<YolkError>> {
+    assert_eq!(optimize_expr("2 * (a + 3)")?, "n=2*a+6");
+    assert_eq!(optimize_expr("(3 - a) * 4")?, "n=12-4*a");
+    assert_eq!(optimize_expr("2 * (a + b)")?, "n=2*(a+b)");
+    // Multiplying by 0.5 truncates, so it cannot be distributed
+    assert_eq!(optimize_expr("0.5 * (a + 3)")?, "n=0.5*(a+3)");
+    Ok(())
+}
+
+#[test]
+fn test_long_chain() {
+    let mut stmts = vec![assign("x", literal(0))];
+    for _ in 0..2000 {
+        let next = YololExpr::Infix {
+            lhs: Box::new(add(ident("x"), literal(1))),
+            op: InfixOp::Mod,
+            rhs: Box::new(literal(7)),
+        };
+        stmts.push(assign("x", next));
+    }
+    let expected: YololProgram = vec![assign("x", literal(2000 % 7))].into();
+    assert_eq!(optimize(stmts.into()), expected);
+}
+
+#[test]
+fn test_reassigned_values() {
+    let program: YololProgram = vec![
+        assign("x", literal(1)),
+        assign("y", add(ident("x"), ident("a"))),
+        assign("x", ident("b")),
+        assign("z", add(ident("x"), literal(2))),
+        assign("x", add(ident("x"), literal(3))),
+    ]
+    .into();
+    let expected: YololProgram = vec![
+        assign("y", add(literal(1), ident("a"))),
+        assign("x", ident("b")),
+        assign("z", add(ident("x"), literal(2))),
+        assign("x", add(ident("x"), literal(3))),
+    ]
+    .into();
+    assert_eq!(optimize(program), expected);
+}
+
+#[test]
+fn test_regroup_additions() {
+    let program: YololProgram = vec![assign(
+        "n",
+        add(ident("a"), add(ident("b"), add(ident("c"), ident("d")))),
+    )]
+    .into();
+    let expected: YololProgram = vec![assign(
+        "n",
+        add(add(add(ident("a"), ident("b")), ident("c")), ident("d")),
+    )]
+    .into();
+    assert_eq!(optimize(program), expected);
+}
@@ -0,0 +1,373 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use yolol_number::YololNumber;
+
+use crate::ast::{YololExpr, YololStmt};
+use crate::optimizer::cost::stmt_cost;
+use crate::optimizer::cse::{self, Temporaries};
+use crate::optimizer::{
+    cheapest_stmt, collect_idents, fold_constant, is_literal, is_total, live_stmts,
+};
+
+/// Optimizes statements by only revisiting the statements whose inputs changed.
+///
+/// Each statement is reduced once when it is added. Afterwards, a statement is
+/// only reduced again if the value of a variable that it reads becomes known.
+pub struct Worklist {
+    stmts: Vec<YololStmt>,
+    // Stores the value of each statement, if it is known at compile time
+    values: Vec<Option<YololNumber>>,
+    // Maps lowercase identifiers to the indices of the statements that assign them, in order
+    assigns: HashMap<String, Vec<usize>>,
+    // Maps lowercase identifiers to the indices of the statements that read them, in order
+    // May contain statements that no longer read the identifier
+    reads: HashMap<String, Vec<usize>>,
+    // Stores the indices of the statements that must be reduced
+    pending: BTreeSet<usize>,
+    // Stores the indices of the statements that changed since they were last hoisted
+    changed: BTreeSet<usize>,
+}
+
+impl Worklist {
+    /// Creates a worklist where every statement must be reduced.
+    pub fn new(stmts: Vec<YololStmt>) -> Worklist {
+        let len = stmts.len();
+        let mut worklist = Worklist {
+            stmts,
+            values: vec![None; len],
+            assigns: HashMap::new(),
+            reads: HashMap::new(),
+            pending: (0..len).collect(),
+            changed: (0..len).collect(),
+        };
+        worklist.index();
+        worklist
+    }
+
+    /// Returns the statements in a worklist.
+    pub fn into_stmts(self) -> Vec<YololStmt> {
+        self.stmts
+    }
+
+    /// Reduces statements until no statement must be reduced.
+    ///
+    /// Statements are reduced in program order, so a statement is only reduced
+    /// once unless the value of a variable that it reads changes later.
+    pub fn propagate(&mut self) {
+        while let Some(&index) = self.pending.iter().next() {
+            self.pending.remove(&index);
+            let vars = self.vars_at(index);
+            let mut stmt = self.stmts[index].clone();
+            loop {
+                let next = cheapest_stmt(stmt.clone(), &vars);
+                if next == stmt {
+                    break;
+                }
+                stmt = next;
+                // Literals cannot be reduced any further
+                let reduced = match &stmt {
+                    YololStmt::Assign { expr, .. } => is_literal(expr),
+                };
+                if reduced {
+                    break;
+                }
+            }
+            let value = match &stmt {
+                YololStmt::Assign { expr, .. } => fold_constant(expr, &vars),
+            };
+            if stmt != self.stmts[index] {
+                self.changed.insert(index);
+                self.stmts[index] = stmt;
+            }
+            if value != self.values[index] {
+                self.values[index] = value;
+                self.revisit_readers(index);
+            }
+        }
+    }
+
+    /// Removes statements that do not contribute to the value of a root.
+    ///
+    /// Returns whether any statements were removed.
+    pub fn eliminate_dead_code(&mut self, roots: &HashSet<String>) -> bool {
+        let live = live_stmts(&self.stmts, roots);
+        if live.iter().all(|&l| l) {
+            return false;
+        }
+        let mut stmts = Vec::new();
+        let mut values = Vec::new();
+        let mut changed = BTreeSet::new();
+        for (index, stmt) in self.stmts.drain(..).enumerate() {
+            if live[index] {
+                if self.changed.contains(&index) {
+                    changed.insert(stmts.len());
+                }
+                stmts.push(stmt);
+                values.push(self.values[index]);
+            }
+        }
+        // Dead statements are never read, so the values of live statements are unchanged
+        self.stmts = stmts;
+        self.values = values;
+        self.changed = changed;
+        self.pending.clear();
+        self.index();
+        true
+    }
+
+    /// Inlines variables that are assigned once and read once, if it makes the program shorter.
+    ///
+    /// Roots and data fields are never inlined. Neither are expressions that might
+    /// cause a runtime error, because a runtime error skips the rest of its line,
+    /// or expressions that read a data field, because other devices may change it.
+    /// Returns whether any variables were inlined.
+    pub fn inline_variables(&mut self, roots: &HashSet<String>) -> bool {
+        let mut removed = vec![false; self.stmts.len()];
+        let mut inlined = BTreeSet::new();
+        for index in 0..self.stmts.len() {
+            let (ident, expr) = match &self.stmts[index] {
+                YololStmt::Assign { ident, expr } => (ident.to_lowercase(), expr.clone()),
+            };
+            if roots.contains(&ident)
+                || ident.starts_with(':')
+                || !is_total(&expr)
+                || self.assigns[&ident].len() != 1
+            {
+                continue;
+            }
+            let reader = match self.only_reader(&ident, &removed) {
+                Some(reader) if reader > index => reader,
+                _ => continue,
+            };
+            // The reader must see the same inputs as the assignment
+            let inputs = reads(&expr);
+            let changed = inputs.iter().any(|input| {
+                input.starts_with(':')
+                    || self.assigns.get(input).map_or(false, |assigns| {
+                        assigns
+                            .iter()
+                            .any(|&i| i > index && i < reader && !removed[i])
+                    })
+            });
+            if changed {
+                continue;
+            }
+            let replaced = match &self.stmts[reader] {
+                YololStmt::Assign {
+                    ident: target,
+                    expr: body,
+                } => YololStmt::Assign {
+                    ident: target.clone(),
+                    expr: Box::new(substitute(body, &ident, &expr)),
+                },
+            };
+            let cost = stmt_cost(&self.stmts[index]) + 1 + stmt_cost(&self.stmts[reader]);
+            if stmt_cost(&replaced) < cost {
+                self.stmts[reader] = replaced;
+                removed[index] = true;
+                inlined.insert(reader);
+                // The reader now reads the inputs, which later statements may inline
+                for input in inputs.into_iter() {
+                    let readers = self.reads.entry(input).or_insert_with(Vec::new);
+                    if let Err(pos) = readers.binary_search(&reader) {
+                        readers.insert(pos, reader);
+                    }
+                }
+            }
+        }
+        if inlined.is_empty() {
+            return false;
+        }
+        let mut stmts = Vec::new();
+        let mut values = Vec::new();
+        let mut changed = BTreeSet::new();
+        let mut pending = BTreeSet::new();
+        for (index, stmt) in self.stmts.drain(..).enumerate() {
+            if removed[index] {
+                continue;
+            }
+            if inlined.contains(&index) {
+                pending.insert(stmts.len());
+            }
+            if inlined.contains(&index) || self.changed.contains(&index) {
+                changed.insert(stmts.len());
+            }
+            stmts.push(stmt);
+            values.push(self.values[index]);
+        }
+        // Inlining keeps the value of every statement, but the readers must be reduced again
+        self.stmts = stmts;
+        self.values = values;
+        self.changed = changed;
+        self.pending = pending;
+        self.index();
+        true
+    }
+
+    /// Hoists repeated subexpressions out of the statements that changed.
+    ///
+    /// Returns whether any subexpressions were hoisted.
+    pub fn eliminate_common_subexprs(&mut self) -> bool {
+        let mut temps = Temporaries::new(&self.stmts);
+        let mut stmts = Vec::new();
+        let mut values = Vec::new();
+        let mut pending = BTreeSet::new();
+        let mut hoisted = false;
+        for (index, stmt) in self.stmts.drain(..).enumerate() {
+            if !self.changed.contains(&index) {
+                stmts.push(stmt);
+                values.push(self.values[index]);
+                continue;
+            }
+            let replaced = cse::hoist_stmt(stmt, &mut temps);
+            if replaced.len() > 1 {
+                // Temporaries are new variables, so only the replaced statements must be reduced
+                hoisted = true;
+                pending.extend(stmts.len()..stmts.len() + replaced.len());
+                values.extend(vec![None; replaced.len() - 1]);
+            }
+            values.push(self.values[index]);
+            stmts.extend(replaced);
+        }
+        self.stmts = stmts;
+        self.values = values;
+        self.changed = pending.clone();
+        self.pending = pending;
+        self.index();
+        hoisted
+    }
+
+    /// Finds the statements that assign and read each identifier.
+    fn index(&mut self) {
+        self.assigns.clear();
+        self.reads.clear();
+        for (index, stmt) in self.stmts.iter().enumerate() {
+            match stmt {
+                YololStmt::Assign { ident, expr } => {
+                    self.assigns
+                        .entry(ident.to_lowercase())
+                        .or_insert_with(Vec::new)
+                        .push(index);
+                    for read in reads(expr).into_iter() {
+                        self.reads.entry(read).or_insert_with(Vec::new).push(index);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Finds the known values of the variables that a statement reads.
+    ///
+    /// A variable has the value of the last statement that assigned it.
+    fn vars_at(&self, index: usize) -> HashMap<String, YololExpr> {
+        let mut vars = HashMap::new();
+        match &self.stmts[index] {
+            YololStmt::Assign { expr, .. } => {
+                for read in reads(expr).into_iter() {
+                    let value = self.assigns.get(&read).and_then(|assigns| {
+                        // The last assignment before this statement
+                        let pos = count_before(assigns, index);
+                        if pos == 0 {
+                            None
+                        } else {
+                            self.values[assigns[pos - 1]]
+                        }
+                    });
+                    if let Some(y) = value {
+                        vars.insert(read, YololExpr::Literal(y));
+                    }
+                }
+            }
+        }
+        vars
+    }
+
+    /// Finds the only statement that reads an identifier, if it is read exactly once.
+    fn only_reader(&self, ident: &str, removed: &[bool]) -> Option<usize> {
+        let mut found = None;
+        for &reader in self.reads.get(ident)?.iter() {
+            if removed[reader] {
+                continue;
+            }
+            let count = match &self.stmts[reader] {
+                YololStmt::Assign { expr, .. } => count_ident(expr, ident),
+            };
+            match (count, found) {
+                (0, _) => (),
+                (1, None) => found = Some(reader),
+                _ => return None,
+            }
+        }
+        found
+    }
+
+    /// Marks the statements that read the value of a statement to be reduced.
+    fn revisit_readers(&mut self, index: usize) {
+        let ident = match &self.stmts[index] {
+            YololStmt::Assign { ident, .. } => ident.to_lowercase(),
+        };
+        // The value is read until the variable is assigned again
+        let next = self.assigns.get(&ident).and_then(|assigns| {
+            let pos = count_until(assigns, index);
+            assigns.get(pos).copied()
+        });
+        if let Some(readers) = self.reads.get(&ident) {
+            let start = count_until(readers, index);
+            for &reader in readers[start..].iter() {
+                if next.map_or(false, |next| reader > next) {
+                    break;
+                }
+                self.pending.insert(reader);
+            }
+        }
+    }
+}
+
+/// Counts the sorted indices that are less than an index.
+fn count_before(indices: &[usize], index: usize) -> usize {
+    match indices.binary_search(&index) {
+        Ok(pos) | Err(pos) => pos,
+    }
+}
+
+/// Counts the sorted indices that are less than or equal to an index.
+fn count_until(indices: &[usize], index: usize) -> usize {
+    match indices.binary_search(&index) {
+        Ok(pos) => pos + 1,
+        Err(pos) => pos,
+    }
+}
+
+/// Returns the lowercase identifiers that an expression reads.
+fn reads(expr: &YololExpr) -> HashSet<String> {
+    let mut idents = HashSet::new();
+    collect_idents(expr, &mut idents);
+    idents
+}
+
+/// Counts the occurrences of a lowercase identifier in an expression.
+fn count_ident(expr: &YololExpr, ident: &str) -> usize {
+    match expr {
+        YololExpr::Prefix { expr, .. } => count_ident(expr, ident),
+        YololExpr::Infix { lhs, rhs, .. } => count_ident(lhs, ident) + count_ident(rhs, ident),
+        YololExpr::Ident(s) => (s.to_lowercase() == ident) as usize,
+        YololExpr::Literal(_) => 0,
+    }
+}
+
+/// Replaces a lowercase identifier in an expression with another expression.
+fn substitute(expr: &YololExpr, ident: &str, value: &YololExpr) -> YololExpr {
+    match expr {
+        YololExpr::Prefix { op, expr } => YololExpr::Prefix {
+            op: *op,
+            expr: Box::new(substitute(expr, ident, value)),
+        },
+        YololExpr::Infix { lhs, op, rhs } => YololExpr::Infix {
+            lhs: Box::new(substitute(lhs, ident, value)),
+            op: *op,
+            rhs: Box::new(substitute(rhs, ident, value)),
+        },
+        YololExpr::Ident(s) if s.to_lowercase() == ident => value.clone(),
+        e => e.clone(),
+    }
+}
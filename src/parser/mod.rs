@@ -1,16 +1,20 @@
+use std::cmp::Ordering;
 use std::str::FromStr;
 
-use num_traits::identities::Zero;
-use pest::iterators::Pair;
+use pest::error::{ErrorVariant, InputLocation, LineColLocation};
+use pest::iterators::{Pair, Pairs};
 use pest::prec_climber::{Assoc, Operator, PrecClimber};
-use pest::Parser;
+use pest::{Parser, RuleType};
 use yolol_number::YololNumber;
 
-use crate::ast::{InfixOp, PrefixOp, YolkExpr, YolkProgram, YolkStmt};
+use crate::ast::{BuiltinOp, FoldOp, InfixOp, PrefixOp, Span, YolkExpr, YolkProgram, YolkStmt};
 use crate::error::YolkError;
 
 #[cfg(test)]
 mod tests;
+mod yolol;
+
+pub use yolol::parse_yolol;
 
 lazy_static! {
     static ref PREC_CLIMBER: PrecClimber<Rule> = build_prec_climber();
@@ -37,68 +41,373 @@ fn build_prec_climber() -> PrecClimber<Rule> {
 #[grammar = "grammar/yolk.pest"]
 pub struct YolkParser;
 
+/// The maximum depth of nested parentheses and brackets.
+///
+/// Deeper nesting is rejected to avoid overflowing the stack.
+const MAX_NESTING: usize = 64;
+
+/// The maximum number of operands in a chain of infix operations.
+///
+/// Each operation nests the chain one level deeper, so longer chains are
+/// rejected to avoid overflowing the stack.
+const MAX_OPERANDS: usize = 256;
+
+/// The largest magnitude of the whole part of a Yolol number.
+const MAX_WHOLE: &str = "922337203685477";
+
+/// The largest fractional part of a Yolol number with the largest whole part.
+const MAX_FRACTION: &str = "5807";
+
 /// Parses a Yolk program from a string.
 ///
-/// # Panics
+/// Parsing continues after a syntax error by skipping the statement that
+/// caused it, so every syntax error in the source is returned.
+pub fn parse(source: &str) -> Result<YolkProgram, Vec<YolkError>> {
+    check_nesting(source).map_err(|e| vec![e])?;
+    let mut errors = Vec::new();
+    // Statements with syntax errors are masked out with whitespace,
+    // which preserves the spans of the remaining statements
+    let mut masked = source.to_string();
+    loop {
+        match YolkParser::parse(Rule::program, &masked) {
+            Ok(pairs) if errors.is_empty() => {
+                let mut stmts = vec![];
+                for pair in pairs {
+                    let stmt = match pair.as_rule() {
+                        Rule::use_stmt => parse_use_stmt(pair),
+                        Rule::import_stmt => parse_import_stmt(pair),
+                        Rule::define_stmt => parse_define_stmt(pair),
+                        Rule::let_stmt => parse_let_stmt(pair),
+                        Rule::set_stmt => parse_set_stmt(pair),
+                        Rule::export_stmt => parse_export_stmt(pair),
+                        Rule::comment | Rule::EOI => continue,
+                        _ => Err(unexpected(&pair, "statement")),
+                    };
+                    match stmt {
+                        Ok(stmt) => stmts.push(stmt),
+                        Err(e) => errors.push(e),
+                    }
+                }
+                return if errors.is_empty() {
+                    Ok(stmts.into())
+                } else {
+                    Err(errors)
+                };
+            }
+            Ok(_) => return Err(errors),
+            Err(e) => {
+                let error = to_syntax_error(&e, rule_to_string);
+                let start = error.span().start;
+                errors.push(error);
+                if !mask_stmt(&mut masked, start) {
+                    return Err(errors);
+                }
+            }
+        }
+    }
+}
+
+/// Checks that parentheses and brackets are not nested too deeply.
+fn check_nesting(source: &str) -> Result<(), YolkError> {
+    let mut depth = 0usize;
+    let mut line = 1;
+    let mut col = 1;
+    let mut in_comment = false;
+    let mut prev = None;
+    for (i, c) in source.char_indices() {
+        match c {
+            '\n' => in_comment = false,
+            '/' if !in_comment && prev == Some('/') => in_comment = true,
+            '(' | '[' if !in_comment => depth += 1,
+            ')' | ']' if !in_comment => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+        if depth > MAX_NESTING {
+            return Err(YolkError::InvalidSyntax {
+                msg: format!("nesting is deeper than {} levels", MAX_NESTING),
+                span: Span {
+                    start: i,
+                    end: i + c.len_utf8(),
+                    line,
+                    col,
+                    file: 0,
+                },
+            });
+        }
+        if c == '\n' {
+            line += 1;
+            col = 1;
+        } else {
+            col += 1;
+        }
+        prev = Some(c);
+    }
+    Ok(())
+}
+
+/// Checks that a chain of infix operations does not have too many operands.
+fn check_operands<R: RuleType>(chain: &Pair<R>) -> Result<(), YolkError> {
+    // Operands and operators alternate, so n operands are separated by n-1 operators
+    let operands = (chain.clone().into_inner().count() + 1) / 2;
+    if operands > MAX_OPERANDS {
+        Err(YolkError::InvalidSyntax {
+            msg: format!("chain has more than {} operands", MAX_OPERANDS),
+            span: to_span(chain),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+fn to_syntax_error<R: RuleType>(e: &pest::error::Error<R>, name: fn(&R) -> String) -> YolkError {
+    let (start, end) = match e.location {
+        InputLocation::Pos(pos) => (pos, pos),
+        InputLocation::Span(span) => span,
+    };
+    let (line, col) = match e.line_col {
+        LineColLocation::Pos(pos) => pos,
+        LineColLocation::Span(pos, _) => pos,
+    };
+    YolkError::InvalidSyntax {
+        msg: syntax_message(&e.variant, name),
+        span: Span {
+            start,
+            end,
+            line,
+            col,
+            file: 0,
+        },
+    }
+}
+
+/// Masks the statement at a position with whitespace.
+///
+/// If there is no statement at the position, the closest preceding statement
+/// is masked instead. Returns false if there was nothing left to mask.
+fn mask_stmt(source: &mut String, pos: usize) -> bool {
+    let bounds = find_stmt_bounds(source);
+    let is_blank = |&(start, end): &(usize, usize)| source[start..end].trim().is_empty();
+    let target = bounds
+        .iter()
+        .rev()
+        .filter(|(start, _)| *start <= pos)
+        .find(|bound| !is_blank(bound));
+    match target {
+        Some(&(start, end)) => {
+            let replacement: String = source[start..end]
+                .chars()
+                .map(|c| match c {
+                    '\n' | '\r' => c.to_string(),
+                    _ => " ".repeat(c.len_utf8()),
+                })
+                .collect();
+            source.replace_range(start..end, &replacement);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Finds the byte ranges of the statements in a source string.
 ///
-/// Panics if the AST is malformed.
-pub fn parse(source: &str) -> Result<YolkProgram, YolkError> {
-    let mut stmts = vec![];
-    let pairs = YolkParser::parse(Rule::program, source)
-        .map_err(|e| YolkError::InvalidSyntax { msg: e.to_string() })?;
-    for pair in pairs {
-        match pair.as_rule() {
-            Rule::import_stmt => stmts.push(parse_import_stmt(pair)),
-            Rule::define_stmt => stmts.push(parse_define_stmt(pair)),
-            Rule::let_stmt => stmts.push(parse_let_stmt(pair)),
-            Rule::comment => (),
-            Rule::EOI => (),
-            _ => panic!("expected rule statement, but got: {:?}", pair),
+/// Statements end at newlines, except for newlines inside parentheses.
+fn find_stmt_bounds(source: &str) -> Vec<(usize, usize)> {
+    let mut bounds = Vec::new();
+    let mut start = 0;
+    let mut depth = 0usize;
+    let mut in_comment = false;
+    let mut prev = None;
+    for (i, c) in source.char_indices() {
+        match c {
+            '\n' => {
+                in_comment = false;
+                if depth == 0 {
+                    bounds.push((start, i));
+                    start = i + 1;
+                }
+            }
+            '/' if !in_comment && prev == Some('/') => in_comment = true,
+            '(' if !in_comment => depth += 1,
+            ')' if !in_comment => depth = depth.saturating_sub(1),
+            _ => (),
         }
+        prev = Some(c);
+    }
+    bounds.push((start, source.len()));
+    bounds
+}
+
+fn syntax_message<R: RuleType>(variant: &ErrorVariant<R>, name: fn(&R) -> String) -> String {
+    let join = |rules: &[R]| rules.iter().map(name).collect::<Vec<String>>().join(", ");
+    match variant {
+        ErrorVariant::ParsingError {
+            positives,
+            negatives,
+        } => match (positives.is_empty(), negatives.is_empty()) {
+            (false, true) => format!("expected {}", join(positives)),
+            (true, false) => format!("unexpected {}", join(negatives)),
+            (false, false) => format!(
+                "unexpected {}; expected {}",
+                join(negatives),
+                join(positives)
+            ),
+            (true, true) => "unknown parsing error".to_string(),
+        },
+        ErrorVariant::CustomError { message } => message.to_string(),
+    }
+}
+
+fn rule_to_string(rule: &Rule) -> String {
+    match rule {
+        Rule::plus => "`+`".to_string(),
+        Rule::minus | Rule::negate => "`-`".to_string(),
+        Rule::colon => "`:`".to_string(),
+        Rule::multiply => "`*`".to_string(),
+        Rule::divide => "`/`".to_string(),
+        Rule::modulo => "`%`".to_string(),
+        Rule::exponent => "`^`".to_string(),
+        Rule::less_than => "`<`".to_string(),
+        Rule::less_equal => "`<=`".to_string(),
+        Rule::greater_than => "`>`".to_string(),
+        Rule::greater_equal => "`>=`".to_string(),
+        Rule::equal => "`==`".to_string(),
+        Rule::not_equal => "`!=`".to_string(),
+        Rule::logical_and => "`and`".to_string(),
+        Rule::logical_or => "`or`".to_string(),
+        Rule::EOI => "end of input".to_string(),
+        _ => format!("{:?}", rule).replace('_', " "),
+    }
+}
+
+fn to_span<R: RuleType>(pair: &Pair<R>) -> Span {
+    let span = pair.as_span();
+    let (line, col) = span.start_pos().line_col();
+    Span {
+        start: span.start(),
+        end: span.end(),
+        line,
+        col,
+        file: 0,
+    }
+}
+
+fn next_pair<'i, R: RuleType>(
+    pairs: &mut Pairs<'i, R>,
+    span: &Span,
+    name: &str,
+) -> Result<Pair<'i, R>, YolkError> {
+    pairs.next().ok_or_else(|| YolkError::InvalidSyntax {
+        msg: format!("missing {}", name),
+        span: *span,
+    })
+}
+
+fn unexpected(pair: &Pair<Rule>, name: &str) -> YolkError {
+    YolkError::InvalidSyntax {
+        msg: format!(
+            "expected {}, but got {}",
+            name,
+            rule_to_string(&pair.as_rule())
+        ),
+        span: to_span(pair),
     }
-    Ok(stmts.into())
 }
 
-fn parse_import_stmt(stmt: Pair<Rule>) -> YolkStmt {
+fn parse_use_stmt(stmt: Pair<Rule>) -> Result<YolkStmt, YolkError> {
+    let span = to_span(&stmt);
     let mut pairs = stmt.into_inner();
-    let ident = pairs.next().expect("failed to unwrap ident from pair");
-    YolkStmt::Import {
+    let path = next_pair(&mut pairs, &span, "path")?;
+    let inner = next_pair(&mut path.into_inner(), &span, "path")?;
+    Ok(YolkStmt::Use {
+        path: inner.as_str().to_string(),
+        span,
+    })
+}
+
+fn parse_import_stmt(stmt: Pair<Rule>) -> Result<YolkStmt, YolkError> {
+    let span = to_span(&stmt);
+    let mut pairs = stmt.into_inner();
+    let ident = next_pair(&mut pairs, &span, "ident")?;
+    Ok(YolkStmt::Import {
         ident: ident.as_str().to_string(),
-    }
+        span,
+    })
 }
 
-fn parse_define_stmt(stmt: Pair<Rule>) -> YolkStmt {
+fn parse_define_stmt(stmt: Pair<Rule>) -> Result<YolkStmt, YolkError> {
+    let span = to_span(&stmt);
     let mut pairs = stmt.into_inner();
-    let ident = pairs.next().expect("failed to unwrap ident from pair");
-    let params = pairs.next().expect("failed to unwrap params from pair");
-    let body = pairs.next().expect("failed to unwrap body from pair");
-    YolkStmt::Define {
+    let ident = next_pair(&mut pairs, &span, "ident")?;
+    let params = next_pair(&mut pairs, &span, "params")?;
+    let body = next_pair(&mut pairs, &span, "body")?;
+    Ok(YolkStmt::Define {
         ident: ident.as_str().to_string(),
         params: params
             .into_inner()
             .map(|x| x.as_str().to_string())
             .collect(),
-        body: Box::new(parse_expr(body)),
-    }
+        body: Box::new(parse_expr(body)?),
+        span,
+    })
 }
 
-fn parse_let_stmt(stmt: Pair<Rule>) -> YolkStmt {
+fn parse_let_stmt(stmt: Pair<Rule>) -> Result<YolkStmt, YolkError> {
+    let span = to_span(&stmt);
     let mut pairs = stmt.into_inner();
-    let ident = pairs.next().expect("failed to unwrap ident from pair");
-    let expr = pairs.next().expect("failed to unwrap expr from pair");
-    YolkStmt::Let {
+    let ident = next_pair(&mut pairs, &span, "ident")?;
+    let expr = next_pair(&mut pairs, &span, "expr")?;
+    Ok(YolkStmt::Let {
         ident: ident.as_str().to_string(),
-        expr: Box::new(parse_expr(expr)),
-    }
+        expr: Box::new(parse_expr(expr)?),
+        span,
+    })
+}
+
+fn parse_set_stmt(stmt: Pair<Rule>) -> Result<YolkStmt, YolkError> {
+    let span = to_span(&stmt);
+    let mut pairs = stmt.into_inner();
+    let ident = next_pair(&mut pairs, &span, "ident")?;
+    let expr = next_pair(&mut pairs, &span, "expr")?;
+    Ok(YolkStmt::Set {
+        ident: ident.as_str().to_string(),
+        expr: Box::new(parse_expr(expr)?),
+        span,
+    })
 }
 
-fn parse_expr(expr: Pair<Rule>) -> YolkExpr {
+fn parse_export_stmt(stmt: Pair<Rule>) -> Result<YolkStmt, YolkError> {
+    let span = to_span(&stmt);
+    let mut pairs = stmt.into_inner();
+    let ident = next_pair(&mut pairs, &span, "ident")?;
+    Ok(YolkStmt::Export {
+        ident: ident.as_str().to_string(),
+        alias: pairs.next().map(|alias| alias.as_str().to_string()),
+        span,
+    })
+}
+
+fn parse_args(args: Pair<Rule>) -> Result<Vec<YolkExpr>, YolkError> {
+    args.into_inner().map(parse_expr).collect()
+}
+
+fn parse_expr(expr: Pair<Rule>) -> Result<YolkExpr, YolkError> {
+    let span = to_span(&expr);
     match expr.as_rule() {
         Rule::prefix_expr => {
             let mut pairs = expr.into_inner();
-            let op = pairs.next().expect("failed to unwrap op from pair");
-            let expr = pairs.next().expect("failed to unwrap expr from pair");
-            YolkExpr::Prefix {
+            let op = next_pair(&mut pairs, &span, "op")?;
+            let mut args = parse_args(next_pair(&mut pairs, &span, "args")?)?;
+            if args.len() != 1 {
+                return Err(YolkError::InvalidSyntax {
+                    msg: format!(
+                        "{} takes 1 argument, but {} were provided",
+                        op.as_str(),
+                        args.len()
+                    ),
+                    span,
+                });
+            }
+            Ok(YolkExpr::Prefix {
                 op: match op.as_rule() {
                     Rule::logical_not => PrefixOp::Not,
                     Rule::abs => PrefixOp::Abs,
@@ -109,67 +418,192 @@ fn parse_expr(expr: Pair<Rule>) -> YolkExpr {
                     Rule::asin => PrefixOp::Asin,
                     Rule::acos => PrefixOp::Acos,
                     Rule::atan => PrefixOp::Atan,
-                    _ => panic!("expected prefix op, but got: {:?}", op),
+                    _ => return Err(unexpected(&op, "prefix op")),
                 },
-                expr: Box::new(parse_expr(expr)),
+                expr: Box::new(args.remove(0)),
+                span,
+            })
+        }
+        Rule::index_expr => {
+            let mut pairs = expr.into_inner();
+            let mut result = parse_expr(next_pair(&mut pairs, &span, "expr")?)?;
+            for (depth, index) in pairs.enumerate() {
+                // Chains of indices are not nested in the grammar, so they must be limited here
+                if depth >= MAX_NESTING {
+                    return Err(YolkError::InvalidSyntax {
+                        msg: format!("nesting is deeper than {} levels", MAX_NESTING),
+                        span,
+                    });
+                }
+                result = parse_index(result, index)?;
+            }
+            Ok(result)
+        }
+        Rule::neg_expr => {
+            let mut pairs: Vec<Pair<Rule>> = expr.into_inner().collect();
+            let operand = pairs.pop().ok_or_else(|| YolkError::InvalidSyntax {
+                msg: "missing expr".to_string(),
+                span,
+            })?;
+            // Chains of negations are not nested in the grammar, so they must be limited here
+            if pairs.len() > MAX_NESTING {
+                return Err(YolkError::InvalidSyntax {
+                    msg: format!("nesting is deeper than {} levels", MAX_NESTING),
+                    span,
+                });
             }
+            let mut result = parse_expr(operand)?;
+            for negate in pairs.iter().rev() {
+                result = YolkExpr::Prefix {
+                    op: PrefixOp::Neg,
+                    span: to_span(negate).join(&result.span()),
+                    expr: Box::new(result),
+                };
+            }
+            Ok(result)
         }
         Rule::fold_expr => {
             let mut pairs = expr.into_inner();
-            let ident = pairs.next().expect("failed to unwrap ident from pair");
-            let args = pairs.next().expect("failed to unwrap args from pair");
-            YolkExpr::Fold {
-                op: match ident.as_str() {
-                    "sum" => InfixOp::Add,
-                    "product" => InfixOp::Mul,
-                    _ => panic!("expected fold, but got: {:?}", ident),
+            let ident = next_pair(&mut pairs, &span, "fold")?;
+            let args = next_pair(&mut pairs, &span, "args")?;
+            Ok(YolkExpr::Fold {
+                op: match ident.as_rule() {
+                    Rule::sum => FoldOp::Sum,
+                    Rule::product => FoldOp::Product,
+                    Rule::min => FoldOp::Min,
+                    Rule::max => FoldOp::Max,
+                    _ => return Err(unexpected(&ident, "fold")),
                 },
-                args: args.into_inner().map(parse_expr).collect(),
-            }
+                args: parse_args(args)?,
+                span,
+            })
+        }
+        Rule::builtin_expr => {
+            let mut pairs = expr.into_inner();
+            let ident = next_pair(&mut pairs, &span, "builtin")?;
+            let args = next_pair(&mut pairs, &span, "args")?;
+            Ok(YolkExpr::Builtin {
+                op: match ident.as_rule() {
+                    Rule::select => BuiltinOp::Select,
+                    Rule::clamp => BuiltinOp::Clamp,
+                    Rule::len => BuiltinOp::Len,
+                    Rule::range => BuiltinOp::Range,
+                    Rule::fill => BuiltinOp::Fill,
+                    _ => return Err(unexpected(&ident, "builtin")),
+                },
+                args: parse_args(args)?,
+                span,
+            })
         }
         Rule::call_expr => {
             let mut pairs = expr.into_inner();
-            let ident = pairs.next().expect("failed to unwrap ident from pair");
-            let args = pairs.next().expect("failed to unwrap args from pair");
-            YolkExpr::Call {
+            let ident = next_pair(&mut pairs, &span, "ident")?;
+            let args = next_pair(&mut pairs, &span, "args")?;
+            Ok(YolkExpr::Call {
                 ident: ident.as_str().to_string(),
-                args: args.into_inner().map(parse_expr).collect(),
-            }
+                args: parse_args(args)?,
+                span,
+            })
         }
-        Rule::infix_expr => PREC_CLIMBER.climb(
-            expr.into_inner(),
-            |pair: Pair<Rule>| parse_expr(pair),
-            |lhs: YolkExpr, op: Pair<Rule>, rhs: YolkExpr| YolkExpr::Infix {
-                lhs: Box::new(lhs),
-                op: match op.as_rule() {
-                    Rule::plus => InfixOp::Add,
-                    Rule::minus => InfixOp::Sub,
-                    Rule::multiply => InfixOp::Mul,
-                    Rule::divide => InfixOp::Div,
-                    Rule::modulo => InfixOp::Mod,
-                    Rule::exponent => InfixOp::Exp,
-                    Rule::less_than => InfixOp::LessThan,
-                    Rule::less_equal => InfixOp::LessEqual,
-                    Rule::greater_than => InfixOp::GreaterThan,
-                    Rule::greater_equal => InfixOp::GreaterEqual,
-                    Rule::equal => InfixOp::Equal,
-                    Rule::not_equal => InfixOp::NotEqual,
-                    Rule::logical_and => InfixOp::And,
-                    Rule::logical_or => InfixOp::Or,
-                    _ => panic!("expected infix op, but got: {:?}", op),
+        Rule::infix_expr => {
+            check_operands(&expr)?;
+            PREC_CLIMBER.climb(
+                expr.into_inner(),
+                parse_expr,
+                |lhs: Result<YolkExpr, YolkError>,
+                 op: Pair<Rule>,
+                 rhs: Result<YolkExpr, YolkError>| {
+                    let (lhs, rhs) = (lhs?, rhs?);
+                    Ok(YolkExpr::Infix {
+                        span: lhs.span().join(&rhs.span()),
+                        lhs: Box::new(lhs),
+                        op: match op.as_rule() {
+                            Rule::plus => InfixOp::Add,
+                            Rule::minus => InfixOp::Sub,
+                            Rule::multiply => InfixOp::Mul,
+                            Rule::divide => InfixOp::Div,
+                            Rule::modulo => InfixOp::Mod,
+                            Rule::exponent => InfixOp::Exp,
+                            Rule::less_than => InfixOp::LessThan,
+                            Rule::less_equal => InfixOp::LessEqual,
+                            Rule::greater_than => InfixOp::GreaterThan,
+                            Rule::greater_equal => InfixOp::GreaterEqual,
+                            Rule::equal => InfixOp::Equal,
+                            Rule::not_equal => InfixOp::NotEqual,
+                            Rule::logical_and => InfixOp::And,
+                            Rule::logical_or => InfixOp::Or,
+                            _ => return Err(unexpected(&op, "infix op")),
+                        },
+                        rhs: Box::new(rhs),
+                    })
                 },
-                rhs: Box::new(rhs),
-            },
-        ),
-        Rule::ident => YolkExpr::Ident(expr.as_str().to_string()),
-        Rule::literal => {
-            //TODO: handle error better
-            YolkExpr::Literal(YololNumber::from_str(expr.as_str()).unwrap_or(YololNumber::zero()))
+            )
         }
+        Rule::ident => Ok(YolkExpr::Ident(expr.as_str().to_string(), span)),
+        Rule::literal => Ok(YolkExpr::Literal(parse_literal(&expr)?, span)),
         Rule::array => {
-            let exprs: Vec<YolkExpr> = expr.into_inner().map(parse_expr).collect();
-            YolkExpr::Array(exprs)
+            let exprs = expr
+                .into_inner()
+                .map(parse_expr)
+                .collect::<Result<Vec<YolkExpr>, YolkError>>()?;
+            Ok(YolkExpr::Array(exprs, span))
         }
-        _ => panic!("expected rule expression, but got: {:?}", expr),
+        _ => Err(unexpected(&expr, "expression")),
+    }
+}
+
+fn parse_index(expr: YolkExpr, index: Pair<Rule>) -> Result<YolkExpr, YolkError> {
+    let span = expr.span().join(&to_span(&index));
+    let mut start = None;
+    let mut end = None;
+    let mut is_slice = false;
+    for pair in index.into_inner() {
+        match pair.as_rule() {
+            Rule::colon => is_slice = true,
+            _ if is_slice => end = Some(Box::new(parse_expr(pair)?)),
+            _ => start = Some(Box::new(parse_expr(pair)?)),
+        }
+    }
+    if is_slice {
+        Ok(YolkExpr::Slice {
+            expr: Box::new(expr),
+            start,
+            end,
+            span,
+        })
+    } else {
+        Ok(YolkExpr::Index {
+            expr: Box::new(expr),
+            index: start.ok_or_else(|| YolkError::InvalidSyntax {
+                msg: "missing index".to_string(),
+                span,
+            })?,
+            span,
+        })
+    }
+}
+
+fn parse_literal(literal: &Pair<Rule>) -> Result<YololNumber, YolkError> {
+    parse_number(literal.as_str(), to_span(literal))
+}
+
+/// Parses a Yolol number, checking that it is in range.
+fn parse_number(text: &str, span: Span) -> Result<YololNumber, YolkError> {
+    let error = |msg: &str| YolkError::InvalidSyntax {
+        msg: msg.to_string(),
+        span,
+    };
+    let mut parts = text.trim_start_matches('-').splitn(2, '.');
+    let whole = parts.next().unwrap_or("").trim_start_matches('0');
+    let fraction = parts.next().unwrap_or("").trim_end_matches('0');
+    // Larger numbers would saturate, so they could not be formatted as they were written
+    let out_of_range = match whole.len().cmp(&MAX_WHOLE.len()) {
+        Ordering::Greater => true,
+        Ordering::Equal => (whole, fraction) > (MAX_WHOLE, MAX_FRACTION),
+        Ordering::Less => false,
+    };
+    if out_of_range {
+        return Err(error("number is out of range"));
     }
+    YololNumber::from_str(text).map_err(|_| error("invalid number"))
 }
@@ -1,16 +1,19 @@
-use num_traits::identities::Zero;
+use num_traits::identities::{One, Zero};
 use yolol_number::YololNumber;
 
-use crate::ast::{YolkExpr, YolkProgram, YolkStmt};
+use crate::ast::{
+    InfixOp, PrefixOp, Span, YolkExpr, YolkProgram, YolkStmt, YololExpr, YololProgram, YololStmt,
+};
 use crate::error::YolkError;
 
 use std::str::FromStr;
 
 #[test]
-fn test_import() -> Result<(), YolkError> {
+fn test_import() -> Result<(), Vec<YolkError>> {
     let parsed: YolkProgram = "import number".parse()?;
     let expected: YolkProgram = vec![YolkStmt::Import {
         ident: "number".to_string(),
+        span: Span::default(),
     }]
     .into();
     assert_eq!(parsed, expected);
@@ -18,14 +21,18 @@ fn test_import() -> Result<(), YolkError> {
 }
 
 #[test]
-fn test_let_number() -> Result<(), YolkError> {
+fn test_let_number() -> Result<(), Vec<YolkError>> {
     let cases = vec!["0", "1", "1.0", "-1", "-1.0", "1.234", "-1.234"];
     for case in cases.iter() {
         println!("case: {}", case);
         let parsed: YolkProgram = format!("let number = {}", case).parse()?;
         let expected: YolkProgram = vec![YolkStmt::Let {
             ident: "number".to_string(),
-            expr: Box::new(YolkExpr::Literal(YololNumber::from_str(case).unwrap())),
+            expr: Box::new(YolkExpr::Literal(
+                YololNumber::from_str(case).unwrap(),
+                Span::default(),
+            )),
+            span: Span::default(),
         }]
         .into();
         assert_eq!(parsed, expected);
@@ -34,14 +41,18 @@ fn test_let_number() -> Result<(), YolkError> {
 }
 
 #[test]
-fn test_let_array() -> Result<(), YolkError> {
+fn test_let_array() -> Result<(), Vec<YolkError>> {
     let parsed: YolkProgram = "let array = [0, number]".parse()?;
     let expected: YolkProgram = vec![YolkStmt::Let {
         ident: "array".to_string(),
-        expr: Box::new(YolkExpr::Array(vec![
-            YolkExpr::Literal(YololNumber::zero()),
-            YolkExpr::Ident("number".to_string()),
-        ])),
+        expr: Box::new(YolkExpr::Array(
+            vec![
+                YolkExpr::Literal(YololNumber::zero(), Span::default()),
+                YolkExpr::Ident("number".to_string(), Span::default()),
+            ],
+            Span::default(),
+        )),
+        span: Span::default(),
     }]
     .into();
     assert_eq!(parsed, expected);
@@ -49,14 +60,76 @@ fn test_let_array() -> Result<(), YolkError> {
 }
 
 #[test]
-fn test_let_prefix() -> Result<(), YolkError> {
+fn test_set_number() -> Result<(), Vec<YolkError>> {
+    let parsed: YolkProgram = "set number = 1".parse()?;
+    let expected: YolkProgram = vec![YolkStmt::Set {
+        ident: "number".to_string(),
+        expr: Box::new(YolkExpr::Literal(YololNumber::one(), Span::default())),
+        span: Span::default(),
+    }]
+    .into();
+    assert_eq!(parsed, expected);
+    Ok(())
+}
+
+#[test]
+fn test_use() -> Result<(), Vec<YolkError>> {
+    let parsed: YolkProgram = "use \"lib/vector.yolk\"".parse()?;
+    let expected: YolkProgram = vec![YolkStmt::Use {
+        path: "lib/vector.yolk".to_string(),
+        span: Span::default(),
+    }]
+    .into();
+    assert_eq!(parsed, expected);
+    Ok(())
+}
+
+#[test]
+fn test_export() -> Result<(), Vec<YolkError>> {
+    let parsed: YolkProgram = "export number\nexport number as other".parse()?;
+    let expected: YolkProgram = vec![
+        YolkStmt::Export {
+            ident: "number".to_string(),
+            alias: None,
+            span: Span::default(),
+        },
+        YolkStmt::Export {
+            ident: "number".to_string(),
+            alias: Some("other".to_string()),
+            span: Span::default(),
+        },
+    ]
+    .into();
+    assert_eq!(parsed, expected);
+    Ok(())
+}
+
+#[test]
+fn test_qualified_call() -> Result<(), Vec<YolkError>> {
+    let parsed: YolkProgram = "let number = vector::dot(a)".parse()?;
+    let expected: YolkProgram = vec![YolkStmt::Let {
+        ident: "number".to_string(),
+        expr: Box::new(YolkExpr::Call {
+            ident: "vector::dot".to_string(),
+            args: vec![YolkExpr::Ident("a".to_string(), Span::default())],
+            span: Span::default(),
+        }),
+        span: Span::default(),
+    }]
+    .into();
+    assert_eq!(parsed, expected);
+    Ok(())
+}
+
+#[test]
+fn test_let_prefix() -> Result<(), Vec<YolkError>> {
     let _: YolkProgram =
         "let number = not (abs (sqrt (sin (cos (tan (asin (acos (atan (0)))))))))".parse()?;
     Ok(())
 }
 
 #[test]
-fn test_let_infix() -> Result<(), YolkError> {
+fn test_let_infix() -> Result<(), Vec<YolkError>> {
     let _: YolkProgram =
         "let number = 1 + 2 - 3 * 4 / 5 % 6 ^ 7 < 8 <= 9 > 10 >= 11 == 12 != 13 and 14 or 15"
             .parse()?;
@@ -64,24 +137,25 @@ fn test_let_infix() -> Result<(), YolkError> {
 }
 
 #[test]
-fn test_let_fold() -> Result<(), YolkError> {
+fn test_let_fold() -> Result<(), Vec<YolkError>> {
     let _: YolkProgram = "let number = sum([0, 1], 2) + product([0, 1], 2)".parse()?;
     Ok(())
 }
 
 #[test]
-fn test_let_call() -> Result<(), YolkError> {
+fn test_let_call() -> Result<(), Vec<YolkError>> {
     let _: YolkProgram = "let number = func(0) + func([0, 1]) + func(number)".parse()?;
     Ok(())
 }
 
 #[test]
-fn test_define() -> Result<(), YolkError> {
+fn test_define() -> Result<(), Vec<YolkError>> {
     let parsed: YolkProgram = "define identity(A) = A".parse()?;
     let expected: YolkProgram = vec![YolkStmt::Define {
         ident: "identity".to_string(),
         params: vec!["A".to_string()],
-        body: Box::new(YolkExpr::Ident("A".to_string())),
+        body: Box::new(YolkExpr::Ident("A".to_string(), Span::default())),
+        span: Span::default(),
     }]
     .into();
     assert_eq!(parsed, expected);
@@ -89,25 +163,67 @@ fn test_define() -> Result<(), YolkError> {
 }
 
 #[test]
-fn test_comment() -> Result<(), YolkError> {
+fn test_comment() -> Result<(), Vec<YolkError>> {
     let _: YolkProgram = "// This is a comment".parse()?;
     Ok(())
 }
 
 #[test]
-fn test_inline_comment() -> Result<(), YolkError> {
+fn test_inline_comment() -> Result<(), Vec<YolkError>> {
     let _: YolkProgram = "import number // This is a comment".parse()?;
     Ok(())
 }
 
 #[test]
-fn test_extra_newlines() -> Result<(), YolkError> {
+fn test_extra_newlines() -> Result<(), Vec<YolkError>> {
     let normal: YolkProgram = "let number = (0)".parse()?;
     let extra: YolkProgram = "let number = (\n0\n)\n".parse()?;
     assert_eq!(normal, extra);
     Ok(())
 }
 
+#[test]
+fn test_spans() -> Result<(), Vec<YolkError>> {
+    let parsed: YolkProgram = "import a\nlet number = a + 1".parse()?;
+    let stmts: Vec<YolkStmt> = parsed.into_iter().collect();
+    let span = stmts[1].span();
+    assert_eq!((span.line, span.col, span.start, span.end), (2, 1, 9, 27));
+    match &stmts[1] {
+        YolkStmt::Let { expr, .. } => {
+            let span = expr.span();
+            assert_eq!((span.line, span.col, span.start, span.end), (2, 14, 22, 27));
+        }
+        _ => panic!("expected let statement"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_syntax_error_span() {
+    match "import a\nlet number = [0 1]"
+        .parse::<YolkProgram>()
+        .err()
+        .as_deref()
+    {
+        Some([YolkError::InvalidSyntax { span, .. }]) => assert_eq!((span.line, span.col), (2, 17)),
+        _ => panic!("expected syntax error"),
+    }
+}
+
+#[test]
+fn test_multiple_syntax_errors() {
+    let source = "let a = [0 1]
+import b
+let c = (
+    1 +
+) // unbalanced
+let d = 1.2345
+let e = 0";
+    let errors = source.parse::<YolkProgram>().expect_err("expected errors");
+    let lines: Vec<usize> = errors.iter().map(|e| e.span().line).collect();
+    assert_eq!(lines, vec![1, 4, 6]);
+}
+
 #[test]
 #[should_panic]
 fn test_invalid_ident() {
@@ -167,3 +283,275 @@ fn test_missing_paren() {
 fn test_missing_whitespace() {
     let _: YolkProgram = "letnumber=0".parse().unwrap();
 }
+
+#[test]
+fn test_max_nesting() -> Result<(), Vec<YolkError>> {
+    let source = format!("let number = {}0{}", "(".repeat(64), ")".repeat(64));
+    let _: YolkProgram = source.parse()?;
+    Ok(())
+}
+
+#[test]
+fn test_too_much_nesting() {
+    let source = format!("let number = {}0{}", "(".repeat(1000), ")".repeat(1000));
+    match source.parse::<YolkProgram>().err().as_deref() {
+        Some([YolkError::InvalidSyntax { span, .. }]) => assert_eq!((span.line, span.col), (1, 78)),
+        _ => panic!("expected syntax error"),
+    }
+}
+
+#[test]
+fn test_max_operands() -> Result<(), Vec<YolkError>> {
+    let source = format!("let number = 0{}", "+a".repeat(255));
+    let _: YolkProgram = source.parse()?;
+    Ok(())
+}
+
+#[test]
+fn test_too_many_operands() {
+    let source = format!("import a\nlet number = a{}", "+a".repeat(256));
+    match source.parse::<YolkProgram>().err().as_deref() {
+        Some([YolkError::InvalidSyntax { span, .. }]) => assert_eq!((span.line, span.col), (2, 14)),
+        _ => panic!("expected syntax error"),
+    }
+    let source = format!("a=0{}", "*b".repeat(256));
+    match source.parse::<YololProgram>().err().as_deref() {
+        Some([YolkError::InvalidSyntax { .. }]) => (),
+        _ => panic!("expected syntax error"),
+    }
+}
+
+#[test]
+fn test_number_out_of_range() {
+    let cases = vec![
+        "99999999999999999999999999999999999",
+        "922337203685478",
+        "922337203685477.999",
+        "-922337203685478.5",
+    ];
+    for case in cases.iter() {
+        println!("case: {}", case);
+        match format!("let number = {}", case)
+            .parse::<YolkProgram>()
+            .err()
+            .as_deref()
+        {
+            Some([YolkError::InvalidSyntax { msg, .. }]) => {
+                assert_eq!(msg, "number is out of range")
+            }
+            _ => panic!("expected syntax error"),
+        }
+    }
+}
+
+#[test]
+fn test_number_in_range() -> Result<(), Vec<YolkError>> {
+    let _: YolkProgram = "let number = 000922337203685477.580".parse()?;
+    Ok(())
+}
+
+#[test]
+fn test_let_neg() -> Result<(), Vec<YolkError>> {
+    let parsed: YolkProgram = "let number = - -a".parse()?;
+    let expected: YolkProgram = vec![YolkStmt::Let {
+        ident: "number".to_string(),
+        expr: Box::new(YolkExpr::Prefix {
+            op: PrefixOp::Neg,
+            expr: Box::new(YolkExpr::Prefix {
+                op: PrefixOp::Neg,
+                expr: Box::new(YolkExpr::Ident("a".to_string(), Span::default())),
+                span: Span::default(),
+            }),
+            span: Span::default(),
+        }),
+        span: Span::default(),
+    }]
+    .into();
+    assert_eq!(parsed, expected);
+    let _: YolkProgram = "let number = -f(a) - -[1, 2] * -(a + b) - -1".parse()?;
+    Ok(())
+}
+
+#[test]
+fn test_let_index() -> Result<(), Vec<YolkError>> {
+    let parsed: YolkProgram = "let number = -a[1] + b[:2][0]".parse()?;
+    let stmts: Vec<YolkStmt> = parsed.into_iter().collect();
+    match &stmts[0] {
+        YolkStmt::Let { expr, .. } => match &**expr {
+            YolkExpr::Infix { lhs, rhs, .. } => {
+                match &**lhs {
+                    YolkExpr::Prefix { expr, .. } => match &**expr {
+                        YolkExpr::Index { .. } => (),
+                        _ => panic!("expected index"),
+                    },
+                    _ => panic!("expected prefix"),
+                }
+                match &**rhs {
+                    YolkExpr::Index { expr, .. } => match &**expr {
+                        YolkExpr::Slice {
+                            start: None,
+                            end: Some(_),
+                            ..
+                        } => (),
+                        _ => panic!("expected slice"),
+                    },
+                    _ => panic!("expected index"),
+                }
+            }
+            _ => panic!("expected infix"),
+        },
+        _ => panic!("expected let statement"),
+    }
+    Ok(())
+}
+
+#[test]
+#[should_panic]
+fn test_missing_index() {
+    let _: YolkProgram = "let number = a[]".parse().unwrap();
+}
+
+fn yolol_assign(ident: &str, expr: YololExpr) -> YololStmt {
+    YololStmt::Assign {
+        ident: ident.to_string(),
+        expr: Box::new(expr),
+    }
+}
+
+fn yolol_infix(lhs: YololExpr, op: InfixOp, rhs: YololExpr) -> YololExpr {
+    YololExpr::Infix {
+        lhs: Box::new(lhs),
+        op,
+        rhs: Box::new(rhs),
+    }
+}
+
+fn yolol_ident(s: &str) -> YololExpr {
+    YololExpr::Ident(s.to_string())
+}
+
+#[test]
+fn test_yolol_assign() -> Result<(), Vec<YolkError>> {
+    let parsed: YololProgram = "a=1 :out=-2.5+b\nc_0=a".parse()?;
+    let expected: YololProgram = vec![
+        yolol_assign("a", YololExpr::Literal(YololNumber::one())),
+        yolol_assign(
+            ":out",
+            yolol_infix(
+                YololExpr::Literal(YololNumber::from_str("-2.5").unwrap()),
+                InfixOp::Add,
+                yolol_ident("b"),
+            ),
+        ),
+        yolol_assign("c_0", yolol_ident("a")),
+    ]
+    .into();
+    assert_eq!(parsed, expected);
+    Ok(())
+}
+
+#[test]
+fn test_yolol_precedence() -> Result<(), Vec<YolkError>> {
+    let parsed: YololProgram = "x=not a+b and -c^d^e".parse()?;
+    let pow = yolol_infix(
+        YololExpr::Prefix {
+            op: PrefixOp::Neg,
+            expr: Box::new(yolol_ident("c")),
+        },
+        InfixOp::Exp,
+        yolol_infix(yolol_ident("d"), InfixOp::Exp, yolol_ident("e")),
+    );
+    let sum = yolol_infix(
+        YololExpr::Prefix {
+            op: PrefixOp::Not,
+            expr: Box::new(yolol_ident("a")),
+        },
+        InfixOp::Add,
+        yolol_ident("b"),
+    );
+    let expected: YololProgram =
+        vec![yolol_assign("x", yolol_infix(sum, InfixOp::And, pow))].into();
+    assert_eq!(parsed, expected);
+    let parsed: YololProgram = "x=a/b/c-d-e".parse()?;
+    assert_eq!(parsed.to_string(), "x=((a/b)/c-d)-e");
+    Ok(())
+}
+
+#[test]
+fn test_yolol_round_trip() -> Result<(), Vec<YolkError>> {
+    let cases = vec![
+        "a=b+c*d",
+        "a=(b+c)*d",
+        "a=b-(c-d)",
+        "a=(b-c)-d",
+        "a=(b/c)/d",
+        "a=b^(c^d)",
+        "a=(b^c)^d",
+        "a=-b^2",
+        "a=-(b^2)",
+        "a=b+-1 c=b-(-1)",
+        "a=-(-b)",
+        "a=not not b",
+        "a=not(b and c) or d",
+        "a=b or c and d",
+        "a=b and c or d",
+        "a=b<c==d>=e f=(b!=c)!=d",
+        "a=abs b+sqrt(c*d)+sin -e",
+        ":out=t0*(t0+1) foo_v1=:out%2",
+    ];
+    for case in cases.iter() {
+        println!("case: {}", case);
+        let parsed: YololProgram = case.parse()?;
+        assert_eq!(parsed.to_string(), *case);
+        assert_eq!(parsed.to_string().parse::<YololProgram>()?, parsed);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_yolol_compound() -> Result<(), Vec<YolkError>> {
+    let parsed: YololProgram = "a+=1 b-=c+d c*=2 d/=3 e%=4 f++ g--".parse()?;
+    assert_eq!(
+        parsed.to_string(),
+        "a=a+1 b=b-(c+d) c=c*2 d=d/3 e=e%4 f=f+1 g=g-1"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_yolol_comments() -> Result<(), Vec<YolkError>> {
+    let parsed: YololProgram = "a=1 // first\n\n// second\nb=2 c=3".parse()?;
+    assert_eq!(parsed.to_string(), "a=1 b=2 c=3");
+    Ok(())
+}
+
+#[test]
+fn test_yolol_keywords() -> Result<(), Vec<YolkError>> {
+    let parsed: YololProgram = "A=NOT B AND notable".parse()?;
+    assert_eq!(parsed.to_string(), "A=not B and notable");
+    assert!("and=1".parse::<YololProgram>().is_err());
+    Ok(())
+}
+
+#[test]
+fn test_yolol_optimize_mixed_case() -> Result<(), Vec<YolkError>> {
+    // Hand-written chips may refer to the same variable as "a" and "A"
+    let parsed: YololProgram = "a=1 A=x b=a+1 B=b*2".parse()?;
+    assert_eq!(parsed.optimize().to_string(), "A=x b=a+1 B=b*2");
+    Ok(())
+}
+
+#[test]
+fn test_yolol_unsupported() {
+    let cases = vec![
+        "goto 1",
+        "if a then b=1 end",
+        "a=\"text\"",
+        "a=b++",
+        "a=1 b",
+    ];
+    for case in cases.iter() {
+        println!("case: {}", case);
+        assert!(case.parse::<YololProgram>().is_err());
+    }
+}
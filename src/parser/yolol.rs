@@ -0,0 +1,238 @@
+use pest::iterators::Pair;
+use pest::prec_climber::{Assoc, Operator, PrecClimber};
+use pest::Parser;
+use yolol_number::YololNumber;
+
+use crate::ast::{InfixOp, PrefixOp, YololExpr, YololProgram, YololStmt};
+use crate::error::YolkError;
+use crate::parser::{
+    check_nesting, check_operands, next_pair, parse_number, to_span, to_syntax_error, MAX_NESTING,
+};
+
+lazy_static! {
+    static ref PREC_CLIMBER: PrecClimber<Rule> = build_prec_climber();
+}
+
+fn build_prec_climber() -> PrecClimber<Rule> {
+    PrecClimber::new(vec![
+        Operator::new(Rule::logical_and, Assoc::Left),
+        Operator::new(Rule::logical_or, Assoc::Left),
+        Operator::new(Rule::equal, Assoc::Left) | Operator::new(Rule::not_equal, Assoc::Left),
+        Operator::new(Rule::less_than, Assoc::Left)
+            | Operator::new(Rule::less_equal, Assoc::Left)
+            | Operator::new(Rule::greater_than, Assoc::Left)
+            | Operator::new(Rule::greater_equal, Assoc::Left),
+        Operator::new(Rule::plus, Assoc::Left) | Operator::new(Rule::minus, Assoc::Left),
+        Operator::new(Rule::multiply, Assoc::Left)
+            | Operator::new(Rule::divide, Assoc::Left)
+            | Operator::new(Rule::modulo, Assoc::Left),
+        Operator::new(Rule::exponent, Assoc::Right),
+    ])
+}
+
+#[derive(Parser)]
+#[grammar = "grammar/yolol.pest"]
+pub struct YololParser;
+
+/// Parses a Yolol program from a string.
+///
+/// Only assignments are supported. Compound assignments (e.g. `a+=1`) and
+/// increments (e.g. `a++`) are expanded into plain assignments.
+pub fn parse_yolol(source: &str) -> Result<YololProgram, Vec<YolkError>> {
+    check_nesting(source).map_err(|e| vec![e])?;
+    let pairs = YololParser::parse(Rule::program, source)
+        .map_err(|e| vec![to_syntax_error(&e, rule_to_string)])?;
+    let mut stmts = Vec::new();
+    let mut errors = Vec::new();
+    for pair in pairs {
+        let stmt = match pair.as_rule() {
+            Rule::assign_stmt => parse_assign_stmt(pair),
+            Rule::compound_stmt => parse_compound_stmt(pair),
+            Rule::increment_stmt => parse_increment_stmt(pair),
+            Rule::unsupported_stmt => Err(unsupported(pair)),
+            Rule::EOI => continue,
+            _ => Err(unexpected(&pair, "statement")),
+        };
+        match stmt {
+            Ok(stmt) => stmts.push(stmt),
+            Err(e) => errors.push(e),
+        }
+    }
+    if errors.is_empty() {
+        Ok(stmts.into())
+    } else {
+        Err(errors)
+    }
+}
+
+fn rule_to_string(rule: &Rule) -> String {
+    match rule {
+        Rule::plus => "`+`".to_string(),
+        Rule::minus | Rule::negate => "`-`".to_string(),
+        Rule::multiply => "`*`".to_string(),
+        Rule::divide => "`/`".to_string(),
+        Rule::modulo => "`%`".to_string(),
+        Rule::exponent => "`^`".to_string(),
+        Rule::less_than => "`<`".to_string(),
+        Rule::less_equal => "`<=`".to_string(),
+        Rule::greater_than => "`>`".to_string(),
+        Rule::greater_equal => "`>=`".to_string(),
+        Rule::equal => "`==`".to_string(),
+        Rule::not_equal => "`!=`".to_string(),
+        Rule::logical_and => "`and`".to_string(),
+        Rule::logical_or => "`or`".to_string(),
+        Rule::EOI => "end of input".to_string(),
+        _ => format!("{:?}", rule).replace('_', " "),
+    }
+}
+
+fn unexpected(pair: &Pair<Rule>, name: &str) -> YolkError {
+    YolkError::InvalidSyntax {
+        msg: format!(
+            "expected {}, but got {}",
+            name,
+            rule_to_string(&pair.as_rule())
+        ),
+        span: to_span(pair),
+    }
+}
+
+fn unsupported(stmt: Pair<Rule>) -> YolkError {
+    let span = to_span(&stmt);
+    let keyword = stmt
+        .into_inner()
+        .next()
+        .map_or(String::new(), |k| k.as_str().to_lowercase());
+    YolkError::InvalidSyntax {
+        msg: format!("`{}` statements are not supported", keyword),
+        span,
+    }
+}
+
+fn parse_assign_stmt(stmt: Pair<Rule>) -> Result<YololStmt, YolkError> {
+    let span = to_span(&stmt);
+    let mut pairs = stmt.into_inner();
+    let ident = next_pair(&mut pairs, &span, "ident")?;
+    let expr = next_pair(&mut pairs, &span, "expr")?;
+    Ok(YololStmt::Assign {
+        ident: ident.as_str().to_string(),
+        expr: Box::new(parse_expr(expr)?),
+    })
+}
+
+fn parse_compound_stmt(stmt: Pair<Rule>) -> Result<YololStmt, YolkError> {
+    let span = to_span(&stmt);
+    let mut pairs = stmt.into_inner();
+    let ident = next_pair(&mut pairs, &span, "ident")?;
+    let op = next_pair(&mut pairs, &span, "op")?;
+    let expr = next_pair(&mut pairs, &span, "expr")?;
+    Ok(YololStmt::Assign {
+        ident: ident.as_str().to_string(),
+        expr: Box::new(YololExpr::Infix {
+            lhs: Box::new(YololExpr::Ident(ident.as_str().to_string())),
+            op: match op.as_rule() {
+                Rule::add_assign => InfixOp::Add,
+                Rule::sub_assign => InfixOp::Sub,
+                Rule::mul_assign => InfixOp::Mul,
+                Rule::div_assign => InfixOp::Div,
+                Rule::mod_assign => InfixOp::Mod,
+                _ => return Err(unexpected(&op, "assignment op")),
+            },
+            rhs: Box::new(parse_expr(expr)?),
+        }),
+    })
+}
+
+fn parse_increment_stmt(stmt: Pair<Rule>) -> Result<YololStmt, YolkError> {
+    let span = to_span(&stmt);
+    let mut pairs = stmt.into_inner();
+    let ident = next_pair(&mut pairs, &span, "ident")?;
+    let op = next_pair(&mut pairs, &span, "op")?;
+    Ok(YololStmt::Assign {
+        ident: ident.as_str().to_string(),
+        expr: Box::new(YololExpr::Infix {
+            lhs: Box::new(YololExpr::Ident(ident.as_str().to_string())),
+            op: match op.as_rule() {
+                Rule::increment => InfixOp::Add,
+                Rule::decrement => InfixOp::Sub,
+                _ => return Err(unexpected(&op, "increment op")),
+            },
+            rhs: Box::new(YololExpr::Literal(YololNumber::from_value(1))),
+        }),
+    })
+}
+
+fn parse_expr(expr: Pair<Rule>) -> Result<YololExpr, YolkError> {
+    let span = to_span(&expr);
+    match expr.as_rule() {
+        Rule::keyword_expr | Rule::neg_expr => {
+            let mut pairs: Vec<Pair<Rule>> = expr.into_inner().collect();
+            let operand = pairs.pop().ok_or_else(|| YolkError::InvalidSyntax {
+                msg: "missing expr".to_string(),
+                span,
+            })?;
+            // Chains of prefix ops are not nested in the grammar, so they must be limited here
+            if pairs.len() > MAX_NESTING {
+                return Err(YolkError::InvalidSyntax {
+                    msg: format!("nesting is deeper than {} levels", MAX_NESTING),
+                    span,
+                });
+            }
+            let mut result = parse_expr(operand)?;
+            for op in pairs.iter().rev() {
+                result = YololExpr::Prefix {
+                    op: match op.as_rule() {
+                        Rule::negate => PrefixOp::Neg,
+                        Rule::logical_not => PrefixOp::Not,
+                        Rule::abs => PrefixOp::Abs,
+                        Rule::sqrt => PrefixOp::Sqrt,
+                        Rule::sin => PrefixOp::Sin,
+                        Rule::cos => PrefixOp::Cos,
+                        Rule::tan => PrefixOp::Tan,
+                        Rule::asin => PrefixOp::Asin,
+                        Rule::acos => PrefixOp::Acos,
+                        Rule::atan => PrefixOp::Atan,
+                        _ => return Err(unexpected(op, "prefix op")),
+                    },
+                    expr: Box::new(result),
+                };
+            }
+            Ok(result)
+        }
+        Rule::infix_expr => {
+            check_operands(&expr)?;
+            PREC_CLIMBER.climb(
+                expr.into_inner(),
+                parse_expr,
+                |lhs: Result<YololExpr, YolkError>,
+                 op: Pair<Rule>,
+                 rhs: Result<YololExpr, YolkError>| {
+                    Ok(YololExpr::Infix {
+                        lhs: Box::new(lhs?),
+                        op: match op.as_rule() {
+                            Rule::plus => InfixOp::Add,
+                            Rule::minus => InfixOp::Sub,
+                            Rule::multiply => InfixOp::Mul,
+                            Rule::divide => InfixOp::Div,
+                            Rule::modulo => InfixOp::Mod,
+                            Rule::exponent => InfixOp::Exp,
+                            Rule::less_than => InfixOp::LessThan,
+                            Rule::less_equal => InfixOp::LessEqual,
+                            Rule::greater_than => InfixOp::GreaterThan,
+                            Rule::greater_equal => InfixOp::GreaterEqual,
+                            Rule::equal => InfixOp::Equal,
+                            Rule::not_equal => InfixOp::NotEqual,
+                            Rule::logical_and => InfixOp::And,
+                            Rule::logical_or => InfixOp::Or,
+                            _ => return Err(unexpected(&op, "infix op")),
+                        },
+                        rhs: Box::new(rhs?),
+                    })
+                },
+            )
+        }
+        Rule::ident | Rule::field => Ok(YololExpr::Ident(expr.as_str().to_string())),
+        Rule::literal => Ok(YololExpr::Literal(parse_number(expr.as_str(), span)?)),
+        _ => Err(unexpected(&expr, "expression")),
+    }
+}
@@ -1,9 +1,14 @@
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
 
-use crate::ast::YololStmt;
+use yolol_number::YololNumber;
+
+use crate::ast::{Span, YololExpr, YololStmt};
 use crate::error::YolkError;
+use crate::optimizer;
 use crate::transpiler::function::Function;
-use crate::transpiler::value::{Value, Vector};
+use crate::transpiler::value::{Scalar, Value, Vector};
+use crate::warning::YolkWarning;
 
 /// Represents a Yolk program environment.
 #[derive(Debug, Clone)]
@@ -12,11 +17,30 @@ pub struct Environment {
     imports: HashSet<String>,
     // Maps variable identifiers to values
     variables: HashMap<String, Value>,
-    // Stores the lowercase identifiers of variables
+    // Maps variable identifiers to the spans where they were defined
+    spans: HashMap<String, Span>,
+    // Maps the lowercase identifiers of variables to their identifiers
     // Used for detecting identifier conflicts
-    lowercase: HashSet<String>,
+    lowercase: HashMap<String, String>,
+    // Maps the lowercase names of exported variables to the spans where they were exported
+    exports: HashMap<String, Span>,
+    // Stores the lowercase names that the program exports
+    // Only export statements assign these names, so variables get versioned names instead
+    reserved: HashSet<String>,
+    // Maps variable identifiers to their current versions
+    // A variable gets a new version when it is set, but cannot be reassigned in place
+    versions: HashMap<String, usize>,
     // Maps function identifiers to functions
     functions: HashMap<String, Function>,
+    // Stores the identifiers of prelude functions that may be redefined
+    overridable: HashSet<String>,
+    // Stores the identifiers of variables and functions with invalid definitions
+    poisoned: HashSet<String>,
+    // Maps lowercase Yolol identifiers to their values, if they are known at compile time
+    literals: HashMap<String, YololExpr>,
+    // Stores the warnings found while converting expressions
+    // Expressions are converted with a shared reference, so this must be a cell
+    warnings: RefCell<Vec<YolkWarning>>,
     // Stores the identifiers of reserved keywords
     keywords: HashSet<String>,
 }
@@ -27,12 +51,24 @@ impl Environment {
         Environment {
             imports: HashSet::new(),
             variables: HashMap::new(),
-            lowercase: HashSet::new(),
+            spans: HashMap::new(),
+            lowercase: HashMap::new(),
+            exports: HashMap::new(),
+            reserved: HashSet::new(),
+            versions: HashMap::new(),
             functions: HashMap::new(),
+            overridable: HashSet::new(),
+            poisoned: HashSet::new(),
+            literals: HashMap::new(),
+            warnings: RefCell::new(Vec::new()),
             keywords: [
+                "use".to_string(),
                 "import".to_string(),
                 "define".to_string(),
                 "let".to_string(),
+                "set".to_string(),
+                "export".to_string(),
+                "as".to_string(),
                 "not".to_string(),
                 "abs".to_string(),
                 "sqrt".to_string(),
@@ -46,6 +82,13 @@ impl Environment {
                 "or".to_string(),
                 "sum".to_string(),
                 "product".to_string(),
+                "min".to_string(),
+                "max".to_string(),
+                "select".to_string(),
+                "clamp".to_string(),
+                "len".to_string(),
+                "range".to_string(),
+                "fill".to_string(),
             ]
             .iter()
             .cloned()
@@ -54,53 +97,79 @@ impl Environment {
     }
 
     /// Gets the value of a variable from an environment.
-    pub fn variable(&self, ident: &str) -> Result<Value, YolkError> {
+    pub fn variable(&self, ident: &str, span: &Span) -> Result<Value, YolkError> {
         match self.variables.get(ident) {
             Some(value) => Ok(value.clone()),
             None => Err(YolkError::UndefinedVariable {
                 var: ident.to_string(),
+                span: *span,
             }),
         }
     }
 
     /// Gets a function from an environment.
-    pub fn function(&self, ident: &str) -> Result<Function, YolkError> {
+    pub fn function(&self, ident: &str, span: &Span) -> Result<Function, YolkError> {
         match self.functions.get(ident) {
             Some(function) => Ok(function.clone()),
             None => Err(YolkError::UndefinedFunction {
                 func: ident.to_string(),
+                span: *span,
             }),
         }
     }
 
+    /// Marks a name as having an invalid definition.
+    ///
+    /// Uses of a poisoned name should not be reported as errors, because the
+    /// invalid definition has already been reported.
+    pub fn poison(&mut self, ident: &str) {
+        self.poisoned.insert(ident.to_string());
+    }
+
+    /// Returns whether a name has an invalid definition.
+    pub fn is_poisoned(&self, ident: &str) -> bool {
+        self.poisoned.contains(ident)
+    }
+
     /// Imports a variable into an environment.
-    pub fn import(&mut self, ident: &str) -> Result<(), YolkError> {
+    pub fn import(&mut self, ident: &str, span: &Span) -> Result<(), YolkError> {
         if self.imports.contains(ident) | self.variables.contains_key(ident) {
             Err(YolkError::ImportExisting {
                 var: ident.to_string(),
+                span: *span,
+                prev: self.span_of(ident),
             })
         } else if self.keywords.contains(ident) {
             Err(YolkError::ImportKeyword {
                 var: ident.to_string(),
+                span: *span,
             })
         } else {
             self.imports.insert(ident.to_string());
             self.variables
                 .insert(ident.to_string(), Value::Scalar(ident.parse()?));
-            self.lowercase.insert(ident.to_string().to_lowercase());
+            self.declare(ident, span);
             Ok(())
         }
     }
 
     /// Defines a function in an environmnent.
+    ///
+    /// Functions defined with `define_overridable` may be redefined once.
     pub fn define(&mut self, ident: &str, function: Function) -> Result<(), YolkError> {
-        if self.functions.contains_key(ident) {
+        if self.overridable.remove(ident) {
+            self.functions.insert(ident.to_string(), function);
+            Ok(())
+        } else if let Some(existing) = self.functions.get(ident) {
             Err(YolkError::DefineExisting {
                 func: ident.to_string(),
+                span: function.span(),
+                prev: existing.span(),
             })
         } else if self.keywords.contains(ident) {
             Err(YolkError::DefineKeyword {
                 func: ident.to_string(),
+                span: function.span(),
             })
         } else {
             self.functions.insert(ident.to_string(), function);
@@ -108,39 +177,211 @@ impl Environment {
         }
     }
 
+    /// Defines a function in an environment that may be redefined later.
+    pub fn define_overridable(&mut self, ident: &str, function: Function) {
+        self.functions.insert(ident.to_string(), function);
+        self.overridable.insert(ident.to_string());
+    }
+
     /// Assigns a value to a variable in an environment.
-    pub fn let_value(&mut self, ident: &str, value: Value) -> Result<Vec<YololStmt>, YolkError> {
+    pub fn let_value(
+        &mut self,
+        ident: &str,
+        value: Value,
+        span: &Span,
+    ) -> Result<Vec<YololStmt>, YolkError> {
         if self.imports.contains(ident) || self.variables.contains_key(ident) {
             Err(YolkError::AssignExisting {
                 var: ident.to_string(),
+                span: *span,
+                prev: self.span_of(ident),
             })
         } else if self.keywords.contains(ident) {
             Err(YolkError::AssignKeyword {
                 var: ident.to_string(),
+                span: *span,
             })
-        } else if self.lowercase.contains(&ident.to_lowercase()) {
+        } else if let Some(other) = self.lowercase.get(&ident.to_lowercase()) {
             Err(YolkError::AssignConflict {
                 var: ident.to_string(),
+                other: other.to_string(),
+                span: *span,
+                prev: self.span_of(other),
+            })
+        } else {
+            if self.reserved.contains(&ident.to_lowercase()) {
+                self.versions.insert(ident.to_string(), 1);
+            }
+            let name = self.yolol_name(ident);
+            let stmts = self.bind(ident, &name, value)?;
+            self.declare(ident, span);
+            Ok(stmts)
+        }
+    }
+
+    /// Reserves a Yolol name for an export statement.
+    ///
+    /// Variables with the same name are assigned to versioned names instead,
+    /// so that the exported value is not overwritten.
+    pub fn reserve(&mut self, name: &str) {
+        self.reserved.insert(name.to_lowercase());
+    }
+
+    /// Exports the current value of a variable from an environment to a Yolol name.
+    ///
+    /// The variable refers to the exported name afterwards, so the value does
+    /// not have to be computed again.
+    pub fn export(
+        &mut self,
+        ident: &str,
+        name: &str,
+        span: &Span,
+    ) -> Result<Vec<YololStmt>, YolkError> {
+        let value = self.variable(ident, span)?;
+        let key = name.to_lowercase();
+        let prev = match self.exports.get(&key) {
+            Some(prev) => Some(*prev),
+            None => self
+                .imports
+                .iter()
+                .find(|import| import.to_lowercase() == key)
+                .map(|import| self.span_of(import)),
+        };
+        if let Some(prev) = prev {
+            Err(YolkError::ExportExisting {
+                name: name.to_string(),
+                span: *span,
+                prev,
+            })
+        } else {
+            self.exports.insert(key, *span);
+            self.bind(ident, name, value)
+        }
+    }
+
+    /// Assigns a new value to an existing variable in an environment.
+    ///
+    /// The variable keeps its Yolol name if it can be reassigned in place.
+    /// Otherwise, the variable gets a new version with a fresh Yolol name.
+    pub fn set_value(
+        &mut self,
+        ident: &str,
+        value: Value,
+        span: &Span,
+    ) -> Result<Vec<YololStmt>, YolkError> {
+        if self.imports.contains(ident) {
+            Err(YolkError::SetImport {
+                var: ident.to_string(),
+                span: *span,
+                prev: self.span_of(ident),
+            })
+        } else if !self.variables.contains_key(ident) {
+            Err(YolkError::UndefinedVariable {
+                var: ident.to_string(),
+                span: *span,
             })
         } else {
-            match value {
-                Value::Scalar(s) => {
-                    let stmt = s.to_assign_stmt(&ident);
-                    self.variables
-                        .insert(ident.to_string(), Value::Scalar(ident.parse()?));
-                    self.lowercase.insert(ident.to_string().to_lowercase());
-                    Ok(vec![stmt])
-                }
-                Value::Vector(v) => {
-                    let stmts = v.to_assign_stmts(&ident);
-                    self.variables.insert(
-                        ident.to_string(),
-                        Value::Vector(Vector::from_expanded_ident(&ident, stmts.len())),
-                    );
-                    self.lowercase.insert(ident.to_string().to_lowercase());
-                    Ok(stmts)
-                }
+            if !self.can_reassign(ident, &value) {
+                *self.versions.entry(ident.to_string()).or_insert(0) += 1;
             }
+            let name = self.yolol_name(ident);
+            self.bind(ident, &name, value)
         }
     }
+
+    /// Returns the Yolol name of the current version of a variable.
+    ///
+    /// Yolk identifiers cannot contain digits, so versioned names such as
+    /// `foo_v1` never conflict with other variables or vector elements.
+    fn yolol_name(&self, ident: &str) -> String {
+        match self.versions.get(ident) {
+            Some(version) if *version > 0 => format!("{}_v{}", ident, version),
+            _ => ident.to_string(),
+        }
+    }
+
+    /// Returns whether a variable can be reassigned without changing its Yolol name.
+    ///
+    /// Vector elements are assigned one at a time, so an element must not
+    /// refer to an element of the old vector that has already been assigned.
+    fn can_reassign(&self, ident: &str, value: &Value) -> bool {
+        match value {
+            Value::Scalar(_) => true,
+            Value::Vector(v) => {
+                let name = self.yolol_name(ident);
+                (0..v.len()).all(|i| {
+                    v.get(i).map_or(true, |s| {
+                        (0..i).all(|j| !s.references(&format!("{}_{}", name, j)))
+                    })
+                })
+            }
+        }
+    }
+
+    /// Binds a variable to a value using a Yolol name.
+    fn bind(&mut self, ident: &str, name: &str, value: Value) -> Result<Vec<YololStmt>, YolkError> {
+        let stmts = match value {
+            Value::Scalar(s) => {
+                let stmt = s.to_assign_stmt(name);
+                self.variables
+                    .insert(ident.to_string(), Value::Scalar(name.parse()?));
+                vec![stmt]
+            }
+            Value::Vector(v) => {
+                let stmts = v.to_assign_stmts(name);
+                self.variables.insert(
+                    ident.to_string(),
+                    Value::Vector(Vector::from_expanded_ident(name, stmts.len())),
+                );
+                stmts
+            }
+        };
+        for stmt in stmts.iter() {
+            self.record_literal(stmt);
+        }
+        Ok(stmts)
+    }
+
+    /// Records a warning.
+    pub fn warn(&self, warning: YolkWarning) {
+        self.warnings.borrow_mut().push(warning);
+    }
+
+    /// Returns the warnings that have been recorded.
+    pub fn warnings(&self) -> Vec<YolkWarning> {
+        self.warnings.borrow().clone()
+    }
+
+    /// Evaluates a scalar at compile time, if possible.
+    pub fn literal(&self, scalar: &Scalar) -> Option<YololNumber> {
+        optimizer::fold_constant(&scalar.as_expr(), &self.literals)
+    }
+
+    /// Records the value of an assignment if it is known at compile time.
+    ///
+    /// A reassigned variable forgets its old value if the new one is unknown.
+    fn record_literal(&mut self, stmt: &YololStmt) {
+        match stmt {
+            YololStmt::Assign { ident, expr } => {
+                match optimizer::fold_constant(expr, &self.literals) {
+                    Some(y) => self
+                        .literals
+                        .insert(ident.to_lowercase(), YololExpr::Literal(y)),
+                    None => self.literals.remove(&ident.to_lowercase()),
+                };
+            }
+        }
+    }
+
+    /// Records where a variable was defined.
+    fn declare(&mut self, ident: &str, span: &Span) {
+        self.spans.insert(ident.to_string(), *span);
+        self.lowercase
+            .insert(ident.to_lowercase(), ident.to_string());
+    }
+
+    /// Gets the span where a variable was defined.
+    fn span_of(&self, ident: &str) -> Span {
+        self.spans.get(ident).copied().unwrap_or_default()
+    }
 }
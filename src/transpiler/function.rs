@@ -2,7 +2,7 @@
 
 use std::collections::HashSet;
 
-use crate::ast::YolkExpr;
+use crate::ast::{Span, YolkExpr};
 use crate::error::YolkError;
 
 /// Represents a Yolk function.
@@ -11,22 +11,56 @@ pub struct Function {
     ident: String,
     params: Vec<String>,
     body: YolkExpr,
+    span: Span,
+    report_at_call: bool,
 }
 
 impl Function {
     /// Creates a new Yolk function.
-    pub fn new(ident: &str, params: &[String], body: &YolkExpr) -> Result<Function, YolkError> {
-        if params.len() < 1 {
-            panic!("function has no parameters");
+    pub fn new(
+        ident: &str,
+        params: &[String],
+        body: &YolkExpr,
+        span: &Span,
+    ) -> Result<Function, Vec<YolkError>> {
+        if params.is_empty() {
+            return Err(vec![YolkError::MalformedAst {
+                msg: format!("function {} has no parameters", ident),
+                span: *span,
+            }]);
         }
         let function = Function {
             ident: ident.to_string(),
             params: params.to_vec(),
             body: body.clone(),
+            span: *span,
+            report_at_call: false,
         };
-        function.check_for_duplicate_params()?;
-        function.check_body_node(&function.body)?;
-        Ok(function)
+        let mut errors = Vec::new();
+        if let Err(e) = function.check_for_duplicate_params() {
+            errors.push(e);
+        }
+        function.check_body_node(&function.body, &mut errors);
+        if errors.is_empty() {
+            Ok(function)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Reports errors in the body of a function at each call, instead of in the body.
+    ///
+    /// This is used for functions whose source is not part of the program.
+    pub fn report_at_call(self) -> Function {
+        Function {
+            report_at_call: true,
+            ..self
+        }
+    }
+
+    /// Returns the span of the definition of a function.
+    pub fn span(&self) -> Span {
+        self.span
     }
 
     fn check_for_duplicate_params(&self) -> Result<(), YolkError> {
@@ -36,55 +70,84 @@ impl Function {
         } else {
             Err(YolkError::DuplicateParams {
                 func: self.ident.to_string(),
+                span: self.span,
             })
         }
     }
 
-    fn check_body_node(&self, node: &YolkExpr) -> Result<(), YolkError> {
+    fn check_body_node(&self, node: &YolkExpr, errors: &mut Vec<YolkError>) {
         match node {
-            YolkExpr::Prefix { op: _, expr } => self.check_body_node(expr)?,
-            YolkExpr::Fold { op: _, args } => {
+            YolkExpr::Prefix { expr, .. } => self.check_body_node(expr, errors),
+            YolkExpr::Fold { args, .. } | YolkExpr::Builtin { args, .. } => {
                 for arg in args.iter() {
-                    self.check_body_node(arg)?;
+                    self.check_body_node(arg, errors);
                 }
             }
-            YolkExpr::Call { ident, args } => {
+            YolkExpr::Call { ident, args, span } => {
                 for arg in args.iter() {
-                    self.check_body_node(arg)?;
+                    self.check_body_node(arg, errors);
                 }
                 // Check for recursive calls
-                if self.ident == ident.to_string() {
-                    return Err(YolkError::RecursiveCall {
+                if self.ident == *ident {
+                    errors.push(YolkError::RecursiveCall {
                         func: self.ident.to_string(),
+                        span: *span,
                     });
                 }
             }
-            YolkExpr::Infix { lhs, op: _, rhs } => {
-                self.check_body_node(lhs)?;
-                self.check_body_node(rhs)?;
+            YolkExpr::Infix { lhs, rhs, .. } => {
+                self.check_body_node(lhs, errors);
+                self.check_body_node(rhs, errors);
             }
-            YolkExpr::Ident(s) => {
+            YolkExpr::Index { expr, index, .. } => {
+                self.check_body_node(expr, errors);
+                self.check_body_node(index, errors);
+            }
+            YolkExpr::Slice {
+                expr, start, end, ..
+            } => {
+                self.check_body_node(expr, errors);
+                for bound in start.iter().chain(end.iter()) {
+                    self.check_body_node(bound, errors);
+                }
+            }
+            YolkExpr::Ident(s, span) => {
                 // Check for undefined local variables
                 if !self.params.contains(s) {
-                    return Err(YolkError::UndefinedVariable { var: s.to_string() });
+                    errors.push(YolkError::UndefinedVariable {
+                        var: s.to_string(),
+                        span: *span,
+                    });
                 }
             }
-            YolkExpr::Array(exprs) => {
+            YolkExpr::Array(exprs, _) => {
                 for expr in exprs.iter() {
-                    self.check_body_node(expr)?;
+                    self.check_body_node(expr, errors);
                 }
             }
             _ => (),
         }
-        Ok(())
     }
 
     /// Calls a function with arguments.
-    pub fn call(&self, args: &[YolkExpr]) -> Result<YolkExpr, YolkError> {
+    pub fn call(&self, args: &[YolkExpr], span: &Span) -> Result<YolkExpr, YolkError> {
         if self.params.len() != args.len() {
             Err(YolkError::WrongNumberOfArgs {
                 func: self.ident.to_string(),
+                expected: self.params.len(),
+                found: args.len(),
+                span: *span,
+                def: if self.report_at_call {
+                    None
+                } else {
+                    Some(self.span)
+                },
             })
+        } else if self.report_at_call {
+            // Arguments are substituted afterwards, so they keep their own spans
+            let mut body = self.body.clone();
+            body.walk_mut(&mut |expr| *expr.span_mut() = *span);
+            Ok(self.replace_params_with_args(args, &body))
         } else {
             Ok(self.replace_params_with_args(args, &self.body))
         }
@@ -92,13 +155,15 @@ impl Function {
 
     fn replace_params_with_args(&self, args: &[YolkExpr], node: &YolkExpr) -> YolkExpr {
         match node {
-            YolkExpr::Prefix { op, expr } => YolkExpr::Prefix {
+            YolkExpr::Prefix { op, expr, span } => YolkExpr::Prefix {
                 op: *op,
                 expr: Box::new(self.replace_params_with_args(args, expr)),
+                span: *span,
             },
             YolkExpr::Fold {
                 op,
                 args: call_args,
+                span,
             } => {
                 let mut replaced_args = Vec::new();
                 for arg in call_args.iter() {
@@ -107,11 +172,28 @@ impl Function {
                 YolkExpr::Fold {
                     op: *op,
                     args: replaced_args,
+                    span: *span,
+                }
+            }
+            YolkExpr::Builtin {
+                op,
+                args: call_args,
+                span,
+            } => {
+                let mut replaced_args = Vec::new();
+                for arg in call_args.iter() {
+                    replaced_args.push(self.replace_params_with_args(args, arg));
+                }
+                YolkExpr::Builtin {
+                    op: *op,
+                    args: replaced_args,
+                    span: *span,
                 }
             }
             YolkExpr::Call {
                 ident,
                 args: call_args,
+                span,
             } => {
                 let mut replaced_args = Vec::new();
                 for arg in call_args.iter() {
@@ -120,28 +202,47 @@ impl Function {
                 YolkExpr::Call {
                     ident: ident.to_string(),
                     args: replaced_args,
+                    span: *span,
                 }
             }
-            YolkExpr::Infix { lhs, op, rhs } => YolkExpr::Infix {
+            YolkExpr::Infix { lhs, op, rhs, span } => YolkExpr::Infix {
                 lhs: Box::new(self.replace_params_with_args(args, lhs)),
                 op: *op,
                 rhs: Box::new(self.replace_params_with_args(args, rhs)),
+                span: *span,
+            },
+            YolkExpr::Index { expr, index, span } => YolkExpr::Index {
+                expr: Box::new(self.replace_params_with_args(args, expr)),
+                index: Box::new(self.replace_params_with_args(args, index)),
+                span: *span,
+            },
+            YolkExpr::Slice {
+                expr,
+                start,
+                end,
+                span,
+            } => YolkExpr::Slice {
+                expr: Box::new(self.replace_params_with_args(args, expr)),
+                start: start
+                    .as_ref()
+                    .map(|start| Box::new(self.replace_params_with_args(args, start))),
+                end: end
+                    .as_ref()
+                    .map(|end| Box::new(self.replace_params_with_args(args, end))),
+                span: *span,
             },
             // Replace local variables with their respective arguments
-            YolkExpr::Ident(s) => {
-                let index = self
-                    .params
-                    .iter()
-                    .position(|param| param == s)
-                    .expect("failed to get index of param");
-                args[index].clone()
-            }
-            YolkExpr::Array(exprs) => {
+            // Bodies are checked for undefined locals, so every ident is a param
+            YolkExpr::Ident(s, _) => match self.params.iter().position(|param| param == s) {
+                Some(index) => args[index].clone(),
+                None => node.clone(),
+            },
+            YolkExpr::Array(exprs, span) => {
                 let mut replaced_exprs = Vec::new();
                 for expr in exprs.iter() {
                     replaced_exprs.push(self.replace_params_with_args(args, expr));
                 }
-                YolkExpr::Array(replaced_exprs)
+                YolkExpr::Array(replaced_exprs, *span)
             }
             _ => node.clone(),
         }
@@ -1,86 +1,428 @@
+use std::collections::HashSet;
+
 use num_traits::identities::{One, Zero};
 use yolol_number::YololNumber;
 
-use crate::ast::{InfixOp, YolkExpr, YolkProgram, YolkStmt, YololProgram};
+use crate::ast::{BuiltinOp, FoldOp, InfixOp, Span, YolkExpr, YolkProgram, YolkStmt, YololProgram};
 use crate::error::YolkError;
+use crate::warning::YolkWarning;
 
 #[cfg(test)]
 mod tests;
 
 mod environment;
 mod function;
+mod prelude;
 mod value;
 
 use environment::Environment;
 use function::Function;
-use value::Value;
+use value::{Scalar, Value, Vector};
+
+/// The maximum length of an array created by a built-in.
+///
+/// Longer arrays cannot fit on a Yolol chip, and would use a lot of memory.
+const MAX_ARRAY_LEN: usize = 1024;
+
+/// The maximum number of scalars that `min` and `max` compare at runtime.
+///
+/// Each scalar is compared to every other scalar, so the output grows quadratically.
+const MAX_EXTREMUM_LEN: usize = 8;
 
 /// Transpiles a Yolk program to a Yolol program
 ///
-/// # Panics
+/// Transpiling continues after an error, so every error in the program is
+/// returned. Names whose definitions failed are not reported again when used.
+///
+/// Malformed programs (e.g. hand-built ASTs with an invalid fold) are reported
+/// as errors instead of panicking.
+pub fn transpile(program: YolkProgram) -> Result<YololProgram, Vec<YolkError>> {
+    transpile_with_warnings(program).0
+}
+
+/// Returns the Yolol names that a Yolk program exports.
+///
+/// Only the final values of exported names (and the elements of exported
+/// arrays) are needed, so they can be used as the roots when optimizing.
+pub fn exported_names(program: &YolkProgram) -> HashSet<String> {
+    let mut names = HashSet::new();
+    for stmt in program.clone().into_iter() {
+        if let YolkStmt::Export { ident, alias, .. } = stmt {
+            names.insert(alias.unwrap_or(ident));
+        }
+    }
+    names
+}
+
+/// Configures how a Yolk program is transpiled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    /// Whether the functions in the prelude are defined (e.g. `dot` and `mean`).
+    pub prelude: bool,
+}
+
+impl Default for Options {
+    /// Creates the default options, which enable the prelude.
+    fn default() -> Self {
+        Options { prelude: true }
+    }
+}
+
+/// Transpiles a Yolk program to a Yolol program, and returns any warnings.
 ///
-/// Panics if the Yolk program is malformed.
-pub fn transpile(program: YolkProgram) -> Result<YololProgram, YolkError> {
+/// Warnings are returned even if the program has errors.
+pub fn transpile_with_warnings(
+    program: YolkProgram,
+) -> (Result<YololProgram, Vec<YolkError>>, Vec<YolkWarning>) {
+    transpile_with_options(program, &Options::default())
+}
+
+/// Transpiles a Yolk program to a Yolol program with options, and returns any warnings.
+pub fn transpile_with_options(
+    program: YolkProgram,
+    options: &Options,
+) -> (Result<YololProgram, Vec<YolkError>>, Vec<YolkWarning>) {
     let mut env = Environment::new();
+    if options.prelude {
+        define_prelude(&mut env);
+    }
+    let stmts: Vec<YolkStmt> = program.into_iter().collect();
+    // Exported names may be exported after other variables with the same name are assigned
+    for stmt in stmts.iter() {
+        if let YolkStmt::Export { ident, alias, .. } = stmt {
+            env.reserve(alias.as_ref().unwrap_or(ident));
+        }
+    }
     let mut assigns = Vec::new();
-    for stmt in program.into_iter() {
+    let mut errors = Vec::new();
+    for stmt in stmts.into_iter() {
         match stmt {
-            YolkStmt::Import { ident } => env.import(&ident)?,
+            YolkStmt::Import { ident, span } => {
+                if let Err(e) = env.import(&ident, &span) {
+                    errors.push(e);
+                }
+            }
             YolkStmt::Define {
                 ident,
                 params,
                 body,
-            } => env.define(&ident, Function::new(&ident, &params, &*body)?)?,
-            YolkStmt::Let { ident, expr } => {
-                assigns.extend(env.let_value(&ident, expr_to_value(&env, &*expr)?)?);
+                span,
+            } => match Function::new(&ident, &params, &*body, &span) {
+                Ok(function) => {
+                    // Functions may only call functions that were defined before them,
+                    // which rules out mutual recursion
+                    let mut call_errors = Vec::new();
+                    check_calls(&env, &ident, &*body, &mut call_errors);
+                    if !call_errors.is_empty() {
+                        errors.extend(call_errors);
+                        env.poison(&ident);
+                    } else if let Err(e) = env.define(&ident, function) {
+                        errors.push(e);
+                    }
+                }
+                Err(es) => {
+                    errors.extend(es);
+                    env.poison(&ident);
+                }
+            },
+            YolkStmt::Let { ident, expr, span } => match expr_to_value(&env, &*expr) {
+                Ok(value) => match env.let_value(&ident, value, &span) {
+                    Ok(stmts) => assigns.extend(stmts),
+                    Err(e) => errors.push(e),
+                },
+                Err(es) => {
+                    errors.extend(es);
+                    env.poison(&ident);
+                }
+            },
+            // Used files are merged into the program by the loader
+            YolkStmt::Use { path, span } => errors.push(YolkError::UseFailed {
+                path,
+                msg: "use statements must be resolved by a loader".to_string(),
+                span,
+            }),
+            YolkStmt::Export { ident, alias, span } => {
+                // Variables with invalid definitions have already been reported
+                if !env.is_poisoned(&ident) {
+                    let name = alias.unwrap_or_else(|| ident.to_string());
+                    match env.export(&ident, &name, &span) {
+                        Ok(stmts) => assigns.extend(stmts),
+                        Err(e) => errors.push(e),
+                    }
+                }
+            }
+            YolkStmt::Set { ident, expr, span } => match expr_to_value(&env, &*expr) {
+                // Variables with invalid definitions have already been reported
+                Ok(_) if env.is_poisoned(&ident) => (),
+                Ok(value) => match env.set_value(&ident, value, &span) {
+                    Ok(stmts) => assigns.extend(stmts),
+                    Err(e) => errors.push(e),
+                },
+                Err(es) => {
+                    errors.extend(es);
+                    env.poison(&ident);
+                }
+            },
+        }
+    }
+    let warnings = dedup_warnings(env.warnings());
+    if errors.is_empty() {
+        (Ok(assigns.into()), warnings)
+    } else {
+        (Err(dedup_errors(errors)), warnings)
+    }
+}
+
+/// Defines the functions in the prelude.
+///
+/// Each function is defined with its namespace, and without a namespace so
+/// that programs can call or redefine it.
+fn define_prelude(env: &mut Environment) {
+    for stmt in prelude::definitions().into_iter() {
+        if let YolkStmt::Define {
+            ident,
+            params,
+            body,
+            span,
+        } = stmt
+        {
+            // The prelude is tested, so its functions are always valid
+            // Programs cannot define namespaced names, so those are never redefined
+            if let Ok(function) = Function::new(&ident, &params, &*body, &span) {
+                // The prelude is not part of the program, so errors are reported at each call
+                let function = function.report_at_call();
+                let name = ident.trim_start_matches(&format!("{}::", prelude::NAMESPACE));
+                env.define_overridable(name, function.clone());
+                env.define_overridable(&ident, function);
+            }
+        }
+    }
+}
+
+/// Removes errors with the same code and span.
+///
+/// Inlining a function argument may report the same error more than once.
+fn dedup_errors(errors: Vec<YolkError>) -> Vec<YolkError> {
+    let mut seen = HashSet::new();
+    errors
+        .into_iter()
+        .filter(|e| {
+            let span = e.span();
+            seen.insert((e.code(), span.start, span.end))
+        })
+        .collect()
+}
+
+/// Removes warnings with the same code and span.
+fn dedup_warnings(warnings: Vec<YolkWarning>) -> Vec<YolkWarning> {
+    let mut seen = HashSet::new();
+    warnings
+        .into_iter()
+        .filter(|w| {
+            let span = w.span();
+            seen.insert((w.code(), span.start, span.end))
+        })
+        .collect()
+}
+
+/// Checks that every function called by a function body is already defined.
+///
+/// Recursive calls are reported by the function itself, and calls to poisoned
+/// functions have already been reported.
+fn check_calls(env: &Environment, func: &str, node: &YolkExpr, errors: &mut Vec<YolkError>) {
+    match node {
+        YolkExpr::Prefix { expr, .. } => check_calls(env, func, expr, errors),
+        YolkExpr::Fold { args, .. } | YolkExpr::Builtin { args, .. } | YolkExpr::Array(args, _) => {
+            for arg in args.iter() {
+                check_calls(env, func, arg, errors);
+            }
+        }
+        YolkExpr::Call { ident, args, span } => {
+            for arg in args.iter() {
+                check_calls(env, func, arg, errors);
+            }
+            if ident != func && !env.is_poisoned(ident) {
+                if let Err(e) = env.function(ident, span) {
+                    errors.push(e);
+                }
             }
         }
+        YolkExpr::Infix { lhs, rhs, .. }
+        | YolkExpr::Index {
+            expr: lhs,
+            index: rhs,
+            ..
+        } => {
+            check_calls(env, func, lhs, errors);
+            check_calls(env, func, rhs, errors);
+        }
+        YolkExpr::Slice {
+            expr, start, end, ..
+        } => {
+            check_calls(env, func, expr, errors);
+            for bound in start.iter().chain(end.iter()) {
+                check_calls(env, func, bound, errors);
+            }
+        }
+        YolkExpr::Ident(..) | YolkExpr::Literal(..) => (),
     }
-    Ok(assigns.into())
 }
 
-fn expr_to_value(env: &Environment, expr: &YolkExpr) -> Result<Value, YolkError> {
+/// Converts an expression to a value.
+///
+/// If the expression refers to a poisoned name, an empty list of errors is
+/// returned, because the name has already been reported.
+fn expr_to_value(env: &Environment, expr: &YolkExpr) -> Result<Value, Vec<YolkError>> {
     match expr {
-        YolkExpr::Prefix { op, expr } => {
+        YolkExpr::Prefix { op, expr, .. } => {
             let value = expr_to_value(env, &expr)?;
             Ok(value.apply_prefix_op(&op))
         }
-        YolkExpr::Fold { op, args } => match op {
-            InfixOp::Add => sum_to_value(env, args),
-            InfixOp::Mul => product_to_value(env, args),
-            _ => panic!("expected fold, but got: {:?}", op),
+        YolkExpr::Fold { op, args, span } => match op {
+            FoldOp::Sum => sum_to_value(env, args),
+            FoldOp::Product => product_to_value(env, args),
+            FoldOp::Min => extremum_to_value(env, args, op, span),
+            FoldOp::Max => extremum_to_value(env, args, op, span),
         },
-        YolkExpr::Call { ident, args } => {
-            let function = env.function(ident)?;
-            let expr = function.call(args)?;
+        YolkExpr::Builtin { op, args, span } => {
+            if args.len() != op.arity() {
+                return Err(vec![YolkError::WrongNumberOfArgs {
+                    func: op.to_string(),
+                    expected: op.arity(),
+                    found: args.len(),
+                    span: *span,
+                    def: None,
+                }]);
+            }
+            let values = exprs_to_values(env, args)?;
+            match op {
+                BuiltinOp::Select => select_to_value(&values[0], &values[1], &values[2], span),
+                BuiltinOp::Clamp => clamp_to_value(&values[0], &values[1], &values[2], span),
+                BuiltinOp::Len => len_to_value(&values[0], &args[0].span()),
+                BuiltinOp::Range => range_to_value(env, &values[0], &values[1], args, span),
+                BuiltinOp::Fill => fill_to_value(env, &values[0], &values[1], args, span),
+            }
+        }
+        YolkExpr::Call { ident, args, span } => {
+            if env.is_poisoned(ident) {
+                return Err(vec![]);
+            }
+            let function = env.function(ident, span).map_err(|e| vec![e])?;
+            let expr = function.call(args, span).map_err(|e| vec![e])?;
             expr_to_value(env, &expr)
         }
-        YolkExpr::Infix { lhs, op, rhs } => {
-            let lhs = expr_to_value(env, &lhs)?;
-            let rhs = expr_to_value(env, &rhs)?;
-            lhs.apply_infix_op(&op, &rhs)
+        YolkExpr::Infix { lhs, op, rhs, span } => {
+            match (expr_to_value(env, &lhs), expr_to_value(env, &rhs)) {
+                (Ok(lhs), Ok(rhs)) => lhs.apply_infix_op(&op, &rhs, span).map_err(|e| vec![e]),
+                (lhs, rhs) => Err(lhs.err().into_iter().chain(rhs.err()).flatten().collect()),
+            }
+        }
+        YolkExpr::Index { expr, index, span } => {
+            let (value, index_value) = match (expr_to_value(env, expr), expr_to_value(env, index)) {
+                (Ok(value), Ok(index_value)) => (value, index_value),
+                (value, index_value) => {
+                    return Err(value
+                        .err()
+                        .into_iter()
+                        .chain(index_value.err())
+                        .flatten()
+                        .collect())
+                }
+            };
+            let vector = to_vector(&value, &expr.span())?;
+            if let Value::Scalar(s) = &index_value {
+                if env.literal(s).is_none() {
+                    return dynamic_index_to_value(env, &vector, &index_value, span);
+                }
+            }
+            let index = to_index(env, &index_value, &index.span())?;
+            vector.get(index).map(Value::Scalar).ok_or_else(|| {
+                vec![YolkError::IndexOutOfRange {
+                    index: index.to_string(),
+                    len: vector.len(),
+                    span: *span,
+                }]
+            })
         }
-        YolkExpr::Ident(s) => env.variable(s),
-        YolkExpr::Literal(y) => Ok(Value::Scalar(y.clone().into())),
-        YolkExpr::Array(exprs) => {
+        YolkExpr::Slice {
+            expr,
+            start,
+            end,
+            span,
+        } => {
+            let value = expr_to_value(env, expr)?;
+            let vector = to_vector(&value, &expr.span())?;
+            let bound = |bound: &Option<Box<YolkExpr>>, default: usize| match bound {
+                Some(bound) => {
+                    let value = expr_to_value(env, bound)?;
+                    to_index(env, &value, &bound.span())
+                }
+                None => Ok(default),
+            };
+            let (start, end) = (bound(start, 0)?, bound(end, vector.len())?);
+            if end > vector.len() {
+                return Err(vec![YolkError::IndexOutOfRange {
+                    index: end.to_string(),
+                    len: vector.len(),
+                    span: *span,
+                }]);
+            }
+            if start >= end {
+                return Err(vec![YolkError::InvalidIndex {
+                    msg: format!("slice from {} to {} is empty", start, end),
+                    span: *span,
+                }]);
+            }
+            Ok(Value::Vector(vector.slice(start, end)))
+        }
+        YolkExpr::Ident(s, span) => {
+            if env.is_poisoned(s) {
+                return Err(vec![]);
+            }
+            env.variable(s, span).map_err(|e| vec![e])
+        }
+        YolkExpr::Literal(y, _) => Ok(Value::Scalar(y.clone().into())),
+        YolkExpr::Array(exprs, _) => {
             let mut scalars = Vec::new();
-            for expr in exprs.iter() {
-                let value = expr_to_value(env, &expr)?;
+            let mut errors = Vec::new();
+            for (expr, value) in exprs.iter().zip(exprs_to_values(env, exprs)?) {
                 match value {
                     Value::Scalar(s) => scalars.push(s),
-                    Value::Vector(_) => return Err(YolkError::NestedArrays),
+                    Value::Vector(_) => errors.push(YolkError::NestedArrays { span: expr.span() }),
                 }
             }
-            Ok(Value::Vector(scalars.into()))
+            if errors.is_empty() {
+                Ok(Value::Vector(scalars.into()))
+            } else {
+                Err(errors)
+            }
         }
     }
 }
 
-fn sum_to_value(env: &Environment, args: &[YolkExpr]) -> Result<Value, YolkError> {
+/// Converts expressions to values, collecting the errors from every expression.
+fn exprs_to_values(env: &Environment, exprs: &[YolkExpr]) -> Result<Vec<Value>, Vec<YolkError>> {
     let mut values = Vec::new();
-    for arg in args.iter() {
-        values.push(expr_to_value(env, arg)?);
+    let mut errors = Vec::new();
+    let mut failed = false;
+    for expr in exprs.iter() {
+        match expr_to_value(env, expr) {
+            Ok(value) => values.push(value),
+            Err(es) => {
+                errors.extend(es);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        Err(errors)
+    } else {
+        Ok(values)
     }
+}
+
+fn sum_to_value(env: &Environment, args: &[YolkExpr]) -> Result<Value, Vec<YolkError>> {
+    let values = exprs_to_values(env, args)?;
     Ok(Value::left_fold(
         &values,
         &InfixOp::Add,
@@ -88,14 +430,300 @@ fn sum_to_value(env: &Environment, args: &[YolkExpr]) -> Result<Value, YolkError
     ))
 }
 
-fn product_to_value(env: &Environment, args: &[YolkExpr]) -> Result<Value, YolkError> {
-    let mut values = Vec::new();
-    for arg in args.iter() {
-        values.push(expr_to_value(env, arg)?);
-    }
+fn product_to_value(env: &Environment, args: &[YolkExpr]) -> Result<Value, Vec<YolkError>> {
+    let values = exprs_to_values(env, args)?;
     Ok(Value::left_fold(
         &values,
         &InfixOp::Mul,
         &YololNumber::one().into(),
     ))
 }
+
+fn extremum_to_value(
+    env: &Environment,
+    args: &[YolkExpr],
+    op: &FoldOp,
+    span: &Span,
+) -> Result<Value, Vec<YolkError>> {
+    let values = exprs_to_values(env, args)?;
+    let (strict, loose) = match op {
+        FoldOp::Max => (InfixOp::GreaterThan, InfixOp::GreaterEqual),
+        _ => (InfixOp::LessThan, InfixOp::LessEqual),
+    };
+    // Constants are compared at compile time, so only one of them is compared at runtime
+    let mut scalars = Vec::new();
+    let mut constant: Option<YololNumber> = None;
+    for s in Value::flatten(&values).into_iter() {
+        match (env.literal(&s), constant) {
+            (Some(y), Some(c)) if op == &FoldOp::Max && y <= c => (),
+            (Some(y), Some(c)) if op == &FoldOp::Min && y >= c => (),
+            (Some(y), _) => constant = Some(y),
+            (None, _) => scalars.push(s),
+        }
+    }
+    scalars.extend(constant.map(Scalar::from));
+    if scalars.len() > MAX_EXTREMUM_LEN {
+        return Err(vec![YolkError::LargeExtremum {
+            func: op.to_string(),
+            len: scalars.len(),
+            max: MAX_EXTREMUM_LEN,
+            span: *span,
+        }]);
+    }
+    Value::select_extremum(&scalars, &strict, &loose).ok_or_else(|| {
+        vec![YolkError::MalformedAst {
+            msg: format!("{} requires at least one number", op),
+            span: *span,
+        }]
+    })
+}
+
+/// Counts the elements of an array.
+fn len_to_value(value: &Value, span: &Span) -> Result<Value, Vec<YolkError>> {
+    match value {
+        Value::Vector(v) => Ok(Value::Scalar(
+            YololNumber::from_value(v.len() as i64).into(),
+        )),
+        Value::Scalar(_) => Err(vec![YolkError::InvalidArgument {
+            func: BuiltinOp::Len.to_string(),
+            msg: "expected an array, not a number".to_string(),
+            span: *span,
+        }]),
+    }
+}
+
+/// Creates a vector of the integers from `start` up to, but not including, `end`.
+fn range_to_value(
+    env: &Environment,
+    start: &Value,
+    end: &Value,
+    args: &[YolkExpr],
+    span: &Span,
+) -> Result<Value, Vec<YolkError>> {
+    let op = BuiltinOp::Range;
+    let start = to_integer(env, &op, start, &args[0].span())?;
+    let end = to_integer(env, &op, end, &args[1].span())?;
+    if end <= start {
+        return Err(vec![YolkError::InvalidArgument {
+            func: op.to_string(),
+            msg: format!("range from {} to {} is empty", start, end),
+            span: *span,
+        }]);
+    }
+    check_array_len(&op, (end - start) as u64, span)?;
+    Ok(Value::Vector(Vector::range(start, end)))
+}
+
+/// Creates a vector by repeating a number.
+fn fill_to_value(
+    env: &Environment,
+    value: &Value,
+    len: &Value,
+    args: &[YolkExpr],
+    span: &Span,
+) -> Result<Value, Vec<YolkError>> {
+    let op = BuiltinOp::Fill;
+    let scalar = match value {
+        Value::Scalar(s) => s,
+        Value::Vector(_) => {
+            return Err(vec![YolkError::NestedArrays {
+                span: args[0].span(),
+            }])
+        }
+    };
+    let len = to_integer(env, &op, len, &args[1].span())?;
+    if len <= 0 {
+        return Err(vec![YolkError::InvalidArgument {
+            func: op.to_string(),
+            msg: format!("length must be positive, but got {}", len),
+            span: args[1].span(),
+        }]);
+    }
+    check_array_len(&op, len as u64, span)?;
+    Ok(Value::Vector(Vector::repeat(scalar, len as usize)))
+}
+
+/// Checks that a generated array is not too long to be useful in Yolol.
+fn check_array_len(op: &BuiltinOp, len: u64, span: &Span) -> Result<(), Vec<YolkError>> {
+    if len > MAX_ARRAY_LEN as u64 {
+        Err(vec![YolkError::InvalidArgument {
+            func: op.to_string(),
+            msg: format!(
+                "array of length {} exceeds the limit of {}",
+                len, MAX_ARRAY_LEN
+            ),
+            span: *span,
+        }])
+    } else {
+        Ok(())
+    }
+}
+
+/// Converts a built-in argument to an integer by evaluating it at compile time.
+fn to_integer(
+    env: &Environment,
+    op: &BuiltinOp,
+    value: &Value,
+    span: &Span,
+) -> Result<i64, Vec<YolkError>> {
+    let invalid = |msg: String| {
+        vec![YolkError::InvalidArgument {
+            func: op.to_string(),
+            msg,
+            span: *span,
+        }]
+    };
+    let scalar = match value {
+        Value::Scalar(s) => s,
+        Value::Vector(_) => return Err(invalid("expected a number, not an array".to_string())),
+    };
+    let y = env.literal(scalar).ok_or_else(|| {
+        vec![YolkError::NonConstantArgument {
+            func: op.to_string(),
+            span: *span,
+        }]
+    })?;
+    let (inner, scale) = (y.get_inner(), YololNumber::one().get_inner());
+    if inner % scale != 0 {
+        return Err(invalid(format!("expected an integer, but got {}", y)));
+    }
+    Ok((inner / scale) as i64)
+}
+
+/// Selects between two values without branching.
+///
+/// `select(c, a, b)` is lowered to `(c!=0)*a+(c==0)*b`, which is applied
+/// element-wise if any of the values are vectors.
+fn select_to_value(
+    cond: &Value,
+    lhs: &Value,
+    rhs: &Value,
+    span: &Span,
+) -> Result<Value, Vec<YolkError>> {
+    let zero = Value::Scalar(YololNumber::zero().into());
+    let select = || -> Result<Value, YolkError> {
+        let truthy = cond.apply_infix_op(&InfixOp::NotEqual, &zero, span)?;
+        let falsy = cond.apply_infix_op(&InfixOp::Equal, &zero, span)?;
+        let lhs = truthy.apply_infix_op(&InfixOp::Mul, lhs, span)?;
+        let rhs = falsy.apply_infix_op(&InfixOp::Mul, rhs, span)?;
+        lhs.apply_infix_op(&InfixOp::Add, &rhs, span)
+    };
+    select().map_err(|e| vec![e])
+}
+
+/// Converts a value to a vector, so that it can be indexed.
+fn to_vector(value: &Value, span: &Span) -> Result<Vector, Vec<YolkError>> {
+    match value {
+        Value::Vector(v) => Ok(v.clone()),
+        Value::Scalar(_) => Err(vec![YolkError::InvalidIndex {
+            msg: "cannot index a number".to_string(),
+            span: *span,
+        }]),
+    }
+}
+
+/// Converts a value to an index by evaluating it at compile time.
+fn to_index(env: &Environment, value: &Value, span: &Span) -> Result<usize, Vec<YolkError>> {
+    let scalar = match value {
+        Value::Scalar(s) => s,
+        Value::Vector(_) => {
+            return Err(vec![YolkError::InvalidIndex {
+                msg: "index must be a number, not an array".to_string(),
+                span: *span,
+            }])
+        }
+    };
+    let y = env
+        .literal(scalar)
+        .ok_or_else(|| vec![YolkError::NonConstantIndex { span: *span }])?;
+    let (inner, scale) = (y.get_inner(), YololNumber::one().get_inner());
+    if inner < 0 || inner % scale != 0 {
+        return Err(vec![YolkError::InvalidIndex {
+            msg: format!("index must be a non-negative integer, but got {}", y),
+            span: *span,
+        }]);
+    }
+    Ok((inner / scale) as usize)
+}
+
+/// Indexes a vector with an index that is only known at runtime.
+///
+/// `a[i]` is lowered to `(i==0)*a_0+(i==1)*a_1+...`, so an index that is out
+/// of range evaluates to 0.
+fn dynamic_index_to_value(
+    env: &Environment,
+    vector: &Vector,
+    index: &Value,
+    span: &Span,
+) -> Result<Value, Vec<YolkError>> {
+    let mut terms = Vec::new();
+    for i in 0..vector.len() {
+        let position = Value::Scalar(YololNumber::from_value(i as i64).into());
+        let selector = index
+            .apply_infix_op(&InfixOp::Equal, &position, span)
+            .map_err(|e| vec![e])?;
+        if let Some(elem) = vector.get(i) {
+            let term = selector
+                .apply_infix_op(&InfixOp::Mul, &Value::Scalar(elem), span)
+                .map_err(|e| vec![e])?;
+            terms.push(term);
+        }
+    }
+    let mut terms = terms.into_iter();
+    let mut result = terms.next().ok_or_else(|| {
+        vec![YolkError::InvalidIndex {
+            msg: "cannot index an empty array".to_string(),
+            span: *span,
+        }]
+    })?;
+    for term in terms {
+        result = result
+            .apply_infix_op(&InfixOp::Add, &term, span)
+            .map_err(|e| vec![e])?;
+    }
+    if let Value::Scalar(s) = &result {
+        env.warn(YolkWarning::DynamicIndex {
+            len: vector.len(),
+            chars: s.as_expr().to_string().len(),
+            span: *span,
+        });
+    }
+    Ok(result)
+}
+
+/// Clamps a value between a lower and upper bound without branching.
+///
+/// `clamp(x, lo, hi)` is lowered to `lo*(x<lo)+hi*(x>hi)+x*(x>=lo)*(x<=hi)`,
+/// which is applied element-wise if any of the values are vectors.
+fn clamp_to_value(
+    value: &Value,
+    lower: &Value,
+    upper: &Value,
+    span: &Span,
+) -> Result<Value, Vec<YolkError>> {
+    let clamp = || -> Result<Value, YolkError> {
+        let below = value.apply_infix_op(&InfixOp::LessThan, lower, span)?;
+        let above = value.apply_infix_op(&InfixOp::GreaterThan, upper, span)?;
+        // Comparisons are 0 or 1, so multiplying them in order is exact
+        let inside = value
+            .apply_infix_op(
+                &InfixOp::Mul,
+                &value.apply_infix_op(&InfixOp::GreaterEqual, lower, span)?,
+                span,
+            )?
+            .apply_infix_op(
+                &InfixOp::Mul,
+                &value.apply_infix_op(&InfixOp::LessEqual, upper, span)?,
+                span,
+            )?;
+        lower
+            .apply_infix_op(&InfixOp::Mul, &below, span)?
+            .apply_infix_op(
+                &InfixOp::Add,
+                &upper.apply_infix_op(&InfixOp::Mul, &above, span)?,
+                span,
+            )?
+            .apply_infix_op(&InfixOp::Add, &inside, span)
+    };
+    clamp().map_err(|e| vec![e])
+}
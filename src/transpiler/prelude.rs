@@ -0,0 +1,26 @@
+use crate::ast::{Span, YolkStmt};
+use crate::loader;
+use crate::parser;
+
+/// The source of the prelude.
+const SOURCE: &str = include_str!("prelude.yolk");
+
+/// The namespace of the functions in the prelude.
+pub const NAMESPACE: &str = "prelude";
+
+/// Parses the definitions in the prelude.
+///
+/// The definitions are namespaced, and their spans are cleared because they
+/// do not refer to the program being transpiled.
+pub fn definitions() -> Vec<YolkStmt> {
+    // The prelude is tested, so parsing can never fail here
+    let mut stmts: Vec<YolkStmt> = parser::parse(SOURCE).unwrap().into_iter().collect();
+    for stmt in stmts.iter_mut() {
+        if let YolkStmt::Define { body, span, .. } = stmt {
+            *span = Span::default();
+            body.walk_mut(&mut |expr| *expr.span_mut() = Span::default());
+        }
+    }
+    loader::qualify(&mut stmts, NAMESPACE);
+    stmts
+}
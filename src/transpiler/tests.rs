@@ -1,18 +1,22 @@
 use num_traits::identities::Zero;
 use yolol_number::YololNumber;
 
-use crate::ast::{InfixOp, YolkExpr};
+use crate::ast::{FoldOp, InfixOp, Span, YolkExpr, YolkProgram, YolkStmt, YololProgram};
 use crate::error::YolkError;
 use crate::transpiler::environment::Environment;
 use crate::transpiler::function::Function;
+use crate::transpiler::prelude;
 use crate::transpiler::value::{Value, Vector};
+use crate::transpiler::{exported_names, transpile_with_options, transpile_with_warnings, Options};
+use crate::warning::YolkWarning;
 
+use std::convert::TryFrom;
 use std::str::FromStr;
 
 #[test]
 fn test_env_import() -> Result<(), YolkError> {
     let mut env = Environment::new();
-    env.import("number")?;
+    env.import("number", &Span::default())?;
     Ok(())
 }
 
@@ -21,9 +25,11 @@ fn test_env_define() -> Result<(), YolkError> {
     let mut env = Environment::new();
     let function = Function::new(
         "function",
-        &vec!["a".to_string()],
-        &YolkExpr::Ident("a".to_string()),
-    )?;
+        &["a".to_string()],
+        &YolkExpr::Ident("a".to_string(), Span::default()),
+        &Span::default(),
+    )
+    .unwrap();
     env.define("function", function)?;
     Ok(())
 }
@@ -32,8 +38,8 @@ fn test_env_define() -> Result<(), YolkError> {
 fn test_env_let_value() -> Result<(), YolkError> {
     let mut env = Environment::new();
     let value = Value::Scalar(YololNumber::zero().into());
-    env.let_value("number", value)?;
-    env.variable("number")?;
+    env.let_value("number", value, &Span::default())?;
+    env.variable("number", &Span::default())?;
     Ok(())
 }
 
@@ -41,22 +47,22 @@ fn test_env_let_value() -> Result<(), YolkError> {
 #[should_panic]
 fn test_env_get_undefined_variable() {
     let env = Environment::new();
-    env.variable("number").unwrap();
+    env.variable("number", &Span::default()).unwrap();
 }
 
 #[test]
 #[should_panic]
 fn test_env_get_undefined_function() {
     let env = Environment::new();
-    env.function("function").unwrap();
+    env.function("function", &Span::default()).unwrap();
 }
 
 #[test]
 #[should_panic]
 fn test_env_import_twice() {
     let mut env = Environment::new();
-    env.import("number").unwrap();
-    env.import("number").unwrap();
+    env.import("number", &Span::default()).unwrap();
+    env.import("number", &Span::default()).unwrap();
 }
 
 #[test]
@@ -64,15 +70,15 @@ fn test_env_import_twice() {
 fn test_env_import_existing() {
     let mut env = Environment::new();
     let value = Value::Scalar(YololNumber::zero().into());
-    env.let_value("number", value).unwrap();
-    env.import("number").unwrap();
+    env.let_value("number", value, &Span::default()).unwrap();
+    env.import("number", &Span::default()).unwrap();
 }
 
 #[test]
 #[should_panic]
 fn test_env_import_keyword() {
     let mut env = Environment::new();
-    env.import("sum").unwrap();
+    env.import("sum", &Span::default()).unwrap();
 }
 
 #[test]
@@ -81,12 +87,13 @@ fn test_env_redefine_function() {
     let mut env = Environment::new();
     let function = Function::new(
         "function",
-        &vec!["a".to_string()],
-        &YolkExpr::Ident("a".to_string()),
+        &["a".to_string()],
+        &YolkExpr::Ident("a".to_string(), Span::default()),
+        &Span::default(),
     )
     .unwrap();
     env.define("function", function.clone()).unwrap();
-    env.define("function", function.clone()).unwrap();
+    env.define("function", function).unwrap();
 }
 
 #[test]
@@ -95,8 +102,9 @@ fn test_env_define_keyword() {
     let mut env = Environment::new();
     let function = Function::new(
         "sum",
-        &vec!["a".to_string()],
-        &YolkExpr::Ident("a".to_string()),
+        &["a".to_string()],
+        &YolkExpr::Ident("a".to_string(), Span::default()),
+        &Span::default(),
     )
     .unwrap();
     env.define("sum", function).unwrap();
@@ -107,8 +115,35 @@ fn test_env_define_keyword() {
 fn test_env_reassign_variable() {
     let mut env = Environment::new();
     let value = Value::Scalar(YololNumber::zero().into());
-    env.let_value("number", value.clone()).unwrap();
-    env.let_value("number", value.clone()).unwrap();
+    env.let_value("number", value.clone(), &Span::default())
+        .unwrap();
+    env.let_value("number", value, &Span::default()).unwrap();
+}
+
+#[test]
+fn test_env_set_value() -> Result<(), YolkError> {
+    let mut env = Environment::new();
+    let value = Value::Scalar(YololNumber::zero().into());
+    env.let_value("number", value.clone(), &Span::default())?;
+    env.set_value("number", value, &Span::default())?;
+    Ok(())
+}
+
+#[test]
+#[should_panic]
+fn test_env_set_undefined_variable() {
+    let mut env = Environment::new();
+    let value = Value::Scalar(YololNumber::zero().into());
+    env.set_value("number", value, &Span::default()).unwrap();
+}
+
+#[test]
+#[should_panic]
+fn test_env_set_import() {
+    let mut env = Environment::new();
+    let value = Value::Scalar(YololNumber::zero().into());
+    env.import("number", &Span::default()).unwrap();
+    env.set_value("number", value, &Span::default()).unwrap();
 }
 
 #[test]
@@ -116,7 +151,7 @@ fn test_env_reassign_variable() {
 fn test_env_assign_to_keyword() {
     let mut env = Environment::new();
     let value = Value::Scalar(YololNumber::zero().into());
-    env.let_value("sum", value.clone()).unwrap();
+    env.let_value("sum", value, &Span::default()).unwrap();
 }
 
 #[test]
@@ -124,14 +159,15 @@ fn test_env_assign_to_keyword() {
 fn test_env_assign_same_lowercase() {
     let mut env = Environment::new();
     let value = Value::Scalar(YololNumber::zero().into());
-    env.let_value("number", value.clone()).unwrap();
-    env.let_value("NUMBER", value.clone()).unwrap();
+    env.let_value("number", value.clone(), &Span::default())
+        .unwrap();
+    env.let_value("NUMBER", value, &Span::default()).unwrap();
 }
 
 #[test]
 fn test_apply_infix_op_scalar_scalar() -> Result<(), YolkError> {
     let value = Value::Scalar("a".parse()?);
-    value.apply_infix_op(&InfixOp::Add, &value)?;
+    value.apply_infix_op(&InfixOp::Add, &value, &Span::default())?;
     Ok(())
 }
 
@@ -139,7 +175,7 @@ fn test_apply_infix_op_scalar_scalar() -> Result<(), YolkError> {
 fn test_apply_infix_op_scalar_vector() -> Result<(), YolkError> {
     let lhs = Value::Scalar("a".parse()?);
     let rhs = Value::Vector(Vector::from_expanded_ident("b", 3));
-    lhs.apply_infix_op(&InfixOp::Add, &rhs)?;
+    lhs.apply_infix_op(&InfixOp::Add, &rhs, &Span::default())?;
     Ok(())
 }
 
@@ -147,7 +183,7 @@ fn test_apply_infix_op_scalar_vector() -> Result<(), YolkError> {
 fn test_apply_infix_op_vector_scalar() -> Result<(), YolkError> {
     let lhs = Value::Vector(Vector::from_expanded_ident("b", 3));
     let rhs = Value::Scalar("a".parse()?);
-    lhs.apply_infix_op(&InfixOp::Add, &rhs)?;
+    lhs.apply_infix_op(&InfixOp::Add, &rhs, &Span::default())?;
     Ok(())
 }
 
@@ -155,7 +191,7 @@ fn test_apply_infix_op_vector_scalar() -> Result<(), YolkError> {
 fn test_apply_infix_op_vector_vector() -> Result<(), YolkError> {
     let lhs = Value::Vector(Vector::from_expanded_ident("a", 3));
     let rhs = Value::Vector(Vector::from_expanded_ident("b", 3));
-    lhs.apply_infix_op(&InfixOp::Add, &rhs)?;
+    lhs.apply_infix_op(&InfixOp::Add, &rhs, &Span::default())?;
     Ok(())
 }
 
@@ -164,21 +200,27 @@ fn test_apply_infix_op_vector_vector() -> Result<(), YolkError> {
 fn test_apply_infix_op_mismatched_arrays() {
     let lhs = Value::Vector(Vector::from_expanded_ident("a", 2));
     let rhs = Value::Vector(Vector::from_expanded_ident("b", 3));
-    lhs.apply_infix_op(&InfixOp::Add, &rhs).unwrap();
+    lhs.apply_infix_op(&InfixOp::Add, &rhs, &Span::default())
+        .unwrap();
 }
 
 #[test]
 fn test_func_new() -> Result<(), YolkError> {
     let function = Function::new(
         "function",
-        &vec!["a".to_string(), "b".to_string(), "c".to_string()],
-        &YolkExpr::Ident("a".to_string()),
+        &["a".to_string(), "b".to_string(), "c".to_string()],
+        &YolkExpr::Ident("a".to_string(), Span::default()),
+        &Span::default(),
+    )
+    .unwrap();
+    function.call(
+        &[
+            YolkExpr::Literal(YololNumber::from_str("0").unwrap(), Span::default()),
+            YolkExpr::Literal(YololNumber::from_str("1").unwrap(), Span::default()),
+            YolkExpr::Literal(YololNumber::from_str("2").unwrap(), Span::default()),
+        ],
+        &Span::default(),
     )?;
-    function.call(&vec![
-        YolkExpr::Literal(YololNumber::from_str("0").unwrap()),
-        YolkExpr::Literal(YololNumber::from_str("1").unwrap()),
-        YolkExpr::Literal(YololNumber::from_str("2").unwrap()),
-    ])?;
     Ok(())
 }
 
@@ -187,8 +229,9 @@ fn test_func_new() -> Result<(), YolkError> {
 fn test_func_duplicate_params() {
     Function::new(
         "function",
-        &vec!["a".to_string(), "a".to_string()],
-        &YolkExpr::Ident("a".to_string()),
+        &["a".to_string(), "a".to_string()],
+        &YolkExpr::Ident("a".to_string(), Span::default()),
+        &Span::default(),
     )
     .unwrap();
 }
@@ -198,11 +241,13 @@ fn test_func_duplicate_params() {
 fn test_func_recursive_call() {
     Function::new(
         "function",
-        &vec!["a".to_string()],
+        &["a".to_string()],
         &YolkExpr::Call {
             ident: "function".to_string(),
-            args: vec![YolkExpr::Ident("a".to_string())],
+            args: vec![YolkExpr::Ident("a".to_string(), Span::default())],
+            span: Span::default(),
         },
+        &Span::default(),
     )
     .unwrap();
 }
@@ -212,8 +257,9 @@ fn test_func_recursive_call() {
 fn test_func_undefined_local() {
     Function::new(
         "function",
-        &vec!["a".to_string()],
-        &YolkExpr::Ident("b".to_string()),
+        &["a".to_string()],
+        &YolkExpr::Ident("b".to_string(), Span::default()),
+        &Span::default(),
     )
     .unwrap();
 }
@@ -223,14 +269,394 @@ fn test_func_undefined_local() {
 fn test_func_wrong_number_of_args() {
     let function = Function::new(
         "function",
-        &vec!["a".to_string()],
-        &YolkExpr::Ident("a".to_string()),
+        &["a".to_string()],
+        &YolkExpr::Ident("a".to_string(), Span::default()),
+        &Span::default(),
     )
     .unwrap();
     function
-        .call(&vec![
-            YolkExpr::Literal(YololNumber::from_str("0").unwrap()),
-            YolkExpr::Literal(YololNumber::from_str("1").unwrap()),
-        ])
+        .call(
+            &[
+                YolkExpr::Literal(YololNumber::from_str("0").unwrap(), Span::default()),
+                YolkExpr::Literal(YololNumber::from_str("1").unwrap(), Span::default()),
+            ],
+            &Span::default(),
+        )
         .unwrap();
 }
+
+#[test]
+fn test_error_span() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "let a = 1\nlet b = a + c".parse()?;
+    match YololProgram::try_from(yolk).err().as_deref() {
+        Some([YolkError::UndefinedVariable { var, span }]) => {
+            assert_eq!(var, "c");
+            assert_eq!((span.line, span.col), (2, 13));
+        }
+        _ => panic!("expected undefined variable"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_multiple_errors() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import a
+        import a
+        define f(x, x) = y
+        let b = a + c + d
+        let e = b + f(1) + [1] + [1, 2]
+        let g = [[1], 2]"
+        .parse()?;
+    let errors = YololProgram::try_from(yolk).expect_err("expected errors");
+    let codes: Vec<&str> = errors.iter().map(YolkError::code).collect();
+    // Uses of b and f are not reported, because their definitions failed
+    assert_eq!(
+        codes,
+        vec!["Y0003", "Y0011", "Y0010", "Y0010", "Y0010", "Y0015"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_func_no_params() {
+    let result = Function::new(
+        "function",
+        &[],
+        &YolkExpr::Literal(YololNumber::zero(), Span::default()),
+        &Span::default(),
+    );
+    match result.err().as_deref() {
+        Some([YolkError::MalformedAst { .. }]) => (),
+        _ => panic!("expected malformed AST"),
+    }
+}
+
+#[test]
+fn test_malformed_fold() {
+    let yolk: YolkProgram = vec![YolkStmt::Let {
+        ident: "number".to_string(),
+        expr: Box::new(YolkExpr::Fold {
+            op: FoldOp::Min,
+            args: vec![],
+            span: Span::default(),
+        }),
+        span: Span::default(),
+    }]
+    .into();
+    match YololProgram::try_from(yolk).err().as_deref() {
+        Some([YolkError::MalformedAst { .. }]) => (),
+        _ => panic!("expected malformed AST"),
+    }
+}
+
+#[test]
+fn test_mutual_recursion() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "define g(a) = f(a)
+        define f(a) = g(a)
+        let number = f(1)"
+        .parse()?;
+    let errors = YololProgram::try_from(yolk).expect_err("expected errors");
+    let codes: Vec<&str> = errors.iter().map(YolkError::code).collect();
+    assert_eq!(codes, vec!["Y0009"]);
+    Ok(())
+}
+
+#[test]
+fn test_select() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import c\nlet a = select(c, 1, 2)\nlet b = select(1, c, 2)".parse()?;
+    let yolol = YololProgram::try_from(yolk)?.optimize();
+    assert_eq!(yolol.to_string(), "a=(c!=0)+(c==0)*2 b=c");
+    Ok(())
+}
+
+#[test]
+fn test_select_wrong_number_of_args() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "let a = select(1, 2)".parse()?;
+    match YololProgram::try_from(yolk).err().as_deref() {
+        Some(
+            [YolkError::WrongNumberOfArgs {
+                expected, found, ..
+            }],
+        ) => assert_eq!((expected, found), (&3, &2)),
+        _ => panic!("expected wrong number of args"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_index_errors() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import i
+        let a = [1, 2, 3]
+        let b = a[3] + a[1:4] + a[2:1]
+        let c = a[i:] + a[0.5] + a[[0]] + i[0]"
+        .parse()?;
+    let errors = YololProgram::try_from(yolk).expect_err("expected errors");
+    let codes: Vec<&str> = errors.iter().map(YolkError::code).collect();
+    assert_eq!(
+        codes,
+        vec!["Y0017", "Y0017", "Y0019", "Y0018", "Y0019", "Y0019", "Y0019"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_dynamic_index() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import i\nlet a = [0, 2, 4]\nlet b = a[i]".parse()?;
+    let (result, warnings) = transpile_with_warnings(yolk);
+    let yolol = result?.optimize();
+    assert_eq!(yolol.to_string(), "a_0=0 a_1=2 a_2=4 b=(i==1)*2+(i==2)*4");
+    match warnings.as_slice() {
+        [YolkWarning::DynamicIndex { len, span, .. }] => {
+            assert_eq!(*len, 3);
+            assert_eq!((span.line, span.col), (3, 9));
+        }
+        _ => panic!("expected dynamic index warning"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_min_max_clamp() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import a
+        import b
+        let c = min(a, b)
+        let d = max([a, b])
+        let e = clamp(a, 0, 1)"
+        .parse()?;
+    let yolol = YololProgram::try_from(yolk)?.optimize();
+    assert_eq!(
+        yolol.to_string(),
+        "c=a*(a<=b)+b*(b<a) d=a*(a>=b)+b*(b>a) e=(a>1)+a*(a>=0)*(a<=1)"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_extremum_constants() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import a
+        let b = min(range(0, 1000))
+        let c = max(a, [1, 3], 2)"
+        .parse()?;
+    let yolol = YololProgram::try_from(yolk)?.optimize();
+    assert_eq!(yolol.to_string(), "b=0 c=a*(a>=3)+3*(3>a)");
+    Ok(())
+}
+
+#[test]
+fn test_large_extremum() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import a
+        let b = min(a, a + 1, a + 2, a + 3, a + 4, a + 5, a + 6, 7, 8)
+        let c = max(a * range(0, 9))"
+        .parse()?;
+    let errors = YololProgram::try_from(yolk).expect_err("expected errors");
+    match errors.as_slice() {
+        [YolkError::LargeExtremum {
+            func, len, span, ..
+        }] => {
+            assert_eq!((func.as_str(), *len), ("max", 9));
+            assert_eq!((span.line, span.col), (3, 17));
+        }
+        _ => panic!("expected large extremum error"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_len_range_fill() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import a
+        let b = len([a, a, a])
+        let c = range(1, 3)
+        let d = fill(a, len(c))"
+        .parse()?;
+    let yolol = YololProgram::try_from(yolk)?.optimize();
+    assert_eq!(yolol.to_string(), "b=3 c_0=1 c_1=2 d_0=a d_1=a");
+    Ok(())
+}
+
+#[test]
+fn test_range_fill_errors() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import a
+        let b = range(a, 2) + range(2, 2) + range(0, 0.5)
+        let c = fill(1, 0) + fill([1], 2) + fill(1, 5000)"
+        .parse()?;
+    let errors = YololProgram::try_from(yolk).expect_err("expected errors");
+    let codes: Vec<&str> = errors.iter().map(YolkError::code).collect();
+    assert_eq!(
+        codes,
+        vec!["Y0026", "Y0020", "Y0020", "Y0020", "Y0015", "Y0020"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_len_errors() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import a\nlet b = len(a)\nlet c = len([1], [2])".parse()?;
+    let errors = YololProgram::try_from(yolk).expect_err("expected errors");
+    let codes: Vec<&str> = errors.iter().map(YolkError::code).collect();
+    assert_eq!(codes, vec!["Y0020", "Y0013"]);
+    Ok(())
+}
+
+#[test]
+fn test_set() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import a
+        let b = a + 1
+        set b = b * 2
+        let c = [a, b]
+        set c = c + 1
+        set c = [c[1], c[0]]
+        let d = b + c[0]"
+        .parse()?;
+    let yolol = YololProgram::try_from(yolk)?;
+    assert_eq!(
+        yolol.to_string(),
+        "b=a+1 b=b*2 c_0=a c_1=b c_0=c_0+1 c_1=c_1+1 c_v1_0=c_1 c_v1_1=c_0\nd=b+c_v1_0"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_set_literal() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import i
+        let a = 1
+        set a = a + 1
+        let b = [1, 2, 3][a]
+        set a = i
+        let c = a + 1"
+        .parse()?;
+    let yolol = YololProgram::try_from(yolk)?.optimize();
+    assert_eq!(yolol.to_string(), "b=3 a=i c=a+1");
+    Ok(())
+}
+
+#[test]
+fn test_prelude_definitions() {
+    for stmt in prelude::definitions().iter() {
+        match stmt {
+            YolkStmt::Define {
+                ident,
+                params,
+                body,
+                span,
+            } => {
+                assert!(ident.starts_with("prelude::"));
+                Function::new(ident, params, body, span).unwrap();
+            }
+            _ => panic!("expected only definitions in the prelude"),
+        }
+    }
+}
+
+#[test]
+fn test_prelude() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "let a = dot([1, 2], [3, 4])
+        let b = prelude::sign(-2)
+        let c = lerp(0, 10, 0.5)"
+        .parse()?;
+    let yolol = YololProgram::try_from(yolk)?.optimize();
+    assert_eq!(yolol.to_string(), "a=11 b=-1 c=5");
+    Ok(())
+}
+
+#[test]
+fn test_prelude_redefine() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "define dot(A, B) = 0
+        let a = dot([1, 2], [3, 4])
+        let b = norm([3, 4])"
+        .parse()?;
+    let yolol = YololProgram::try_from(yolk)?.optimize();
+    assert_eq!(yolol.to_string(), "a=0 b=5");
+    Ok(())
+}
+
+#[test]
+fn test_prelude_errors() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import x
+        let a = cross([1, 2], [x, x])
+        let b = norm([1, y])
+        let c = dot(1)"
+        .parse()?;
+    let errors = YololProgram::try_from(yolk).expect_err("expected errors");
+    let locations: Vec<(&str, usize, usize)> = errors
+        .iter()
+        .map(|e| (e.code(), e.span().line, e.span().col))
+        .collect();
+    // Errors in prelude bodies are reported at the call, and errors in arguments where they are
+    assert_eq!(
+        locations,
+        vec![("Y0017", 2, 17), ("Y0010", 3, 26), ("Y0013", 4, 17)]
+    );
+    match errors.last() {
+        Some(YolkError::WrongNumberOfArgs { def, .. }) => assert_eq!(*def, None),
+        _ => panic!("expected wrong number of args"),
+    }
+    Ok(())
+}
+
+#[test]
+fn test_no_prelude() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "let a = dot([1, 2], [3, 4])".parse()?;
+    let options = Options { prelude: false };
+    let errors = transpile_with_options(yolk, &options)
+        .0
+        .expect_err("expected errors");
+    let codes: Vec<&str> = errors.iter().map(YolkError::code).collect();
+    assert_eq!(codes, vec!["Y0009"]);
+    Ok(())
+}
+
+#[test]
+fn test_export() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import a
+        let b = a + 1
+        let c = [b, b * 2]
+        export c as d
+        let e = sum(c) + b
+        export e"
+        .parse()?;
+    let roots = exported_names(&yolk);
+    let yolol = YololProgram::try_from(yolk)?.optimize_with_roots(&roots);
+    assert_eq!(yolol.to_string(), "b=a+1 d_0=b d_1=b*2 e=d_0+d_1+b");
+    Ok(())
+}
+
+#[test]
+fn test_export_chain() -> Result<(), Vec<YolkError>> {
+    let names: Vec<String> = (b'a'..=b'y').map(|c| format!("v{}", c as char)).collect();
+    let mut source = "import a\nlet va = a".to_string();
+    for pair in names.windows(2) {
+        source.push_str(&format!("\nlet {1} = {0} * {0} + 1", pair[0], pair[1]));
+    }
+    source.push_str("\nexport vy");
+    let yolk: YolkProgram = source.parse()?;
+    let roots = exported_names(&yolk);
+    let yolol = YololProgram::try_from(yolk)?.optimize_with_roots(&roots);
+    assert_eq!(yolol.clone().into_iter().count(), names.len());
+    assert!(yolol.to_string().ends_with("vy=vx*vx+1"));
+
+    let mut source = "import a\nlet x = a".to_string();
+    for _ in 0..30 {
+        source.push_str("\nset x = x * x + 1");
+    }
+    source.push_str("\nexport x");
+    let yolk: YolkProgram = source.parse()?;
+    let roots = exported_names(&yolk);
+    let yolol = YololProgram::try_from(yolk)?.optimize_with_roots(&roots);
+    assert_eq!(yolol.into_iter().count(), 32);
+    Ok(())
+}
+
+#[test]
+fn test_export_errors() -> Result<(), Vec<YolkError>> {
+    let yolk: YolkProgram = "import a
+        let b = 1
+        export b
+        export b
+        export b as A
+        export c"
+        .parse()?;
+    let errors = YololProgram::try_from(yolk).expect_err("expected errors");
+    let codes: Vec<&str> = errors.iter().map(YolkError::code).collect();
+    assert_eq!(codes, vec!["Y0025", "Y0025", "Y0010"]);
+    Ok(())
+}
@@ -1,6 +1,6 @@
 use yolol_number::YololNumber;
 
-use crate::ast::{InfixOp, PrefixOp, YololExpr, YololStmt};
+use crate::ast::{InfixOp, PrefixOp, Span, YololExpr, YololStmt};
 use crate::error::YolkError;
 
 use std::str::FromStr;
@@ -27,7 +27,12 @@ impl Value {
     /// repeated to produce another vector of the same length.
     ///
     /// If both values are vectors, they must have the same length.
-    pub fn apply_infix_op(&self, op: &InfixOp, other: &Value) -> Result<Value, YolkError> {
+    pub fn apply_infix_op(
+        &self,
+        op: &InfixOp,
+        other: &Value,
+        span: &Span,
+    ) -> Result<Value, YolkError> {
         match (self, other) {
             (Value::Scalar(lhs), Value::Scalar(rhs)) => {
                 Ok(Value::Scalar(lhs.apply_infix_op(op, &rhs)))
@@ -46,7 +51,11 @@ impl Value {
             }
             (Value::Vector(lhs), Value::Vector(rhs)) => {
                 if lhs.scalars.len() != rhs.scalars.len() {
-                    Err(YolkError::MismatchedArrays)
+                    Err(YolkError::MismatchedArrays {
+                        lhs_len: lhs.scalars.len(),
+                        rhs_len: rhs.scalars.len(),
+                        span: *span,
+                    })
                 } else {
                     Ok(Value::Vector(lhs.apply_infix_op(op, &rhs)))
                 }
@@ -56,18 +65,51 @@ impl Value {
 
     /// Left-folds values to a single value.
     pub fn left_fold(values: &[Value], op: &InfixOp, start: &Scalar) -> Value {
-        let mut result = start.clone();
+        // The result is moved into each operation, so folding is linear in its size
+        let mut result = start.as_expr();
+        for s in Value::flatten(values).into_iter() {
+            result = YololExpr::Infix {
+                lhs: Box::new(result),
+                op: *op,
+                rhs: Box::new(s.expr),
+            };
+        }
+        Value::Scalar(Scalar { expr: result })
+    }
+
+    /// Flattens values into a list of scalars.
+    pub fn flatten(values: &[Value]) -> Vec<Scalar> {
+        let mut scalars = Vec::new();
         for value in values.iter() {
             match value {
-                Value::Scalar(s) => result = result.apply_infix_op(op, s),
-                Value::Vector(v) => {
-                    for s in v.scalars.iter() {
-                        result = result.apply_infix_op(op, s);
-                    }
+                Value::Scalar(s) => scalars.push(s.clone()),
+                Value::Vector(v) => scalars.extend(v.scalars.iter().cloned()),
+            }
+        }
+        scalars
+    }
+
+    /// Selects the smallest or largest of some scalars.
+    ///
+    /// A scalar is selected if it beats every earlier scalar with `strict` and
+    /// every later scalar with `loose` (e.g. `<` and `<=` for the smallest),
+    /// so exactly one scalar is selected when there are ties.
+    pub fn select_extremum(scalars: &[Scalar], strict: &InfixOp, loose: &InfixOp) -> Option<Value> {
+        let mut result: Option<Scalar> = None;
+        for (i, s) in scalars.iter().enumerate() {
+            let mut term = s.clone();
+            for (j, t) in scalars.iter().enumerate() {
+                if i != j {
+                    let op = if j < i { strict } else { loose };
+                    term = term.apply_infix_op(&InfixOp::Mul, &s.apply_infix_op(op, t));
                 }
             }
+            result = Some(match result {
+                Some(r) => r.apply_infix_op(&InfixOp::Add, &term),
+                None => term,
+            });
         }
-        Value::Scalar(result)
+        result.map(Value::Scalar)
     }
 }
 
@@ -107,10 +149,23 @@ impl Scalar {
     }
 
     /// Returns a scalar as a Yolol expression.
-    fn as_expr(&self) -> YololExpr {
+    pub fn as_expr(&self) -> YololExpr {
         self.expr.clone()
     }
 
+    /// Returns whether a scalar refers to a Yolol identifier.
+    pub fn references(&self, ident: &str) -> bool {
+        fn visit(expr: &YololExpr, ident: &str) -> bool {
+            match expr {
+                YololExpr::Ident(s) => s == ident,
+                YololExpr::Literal(_) => false,
+                YololExpr::Prefix { expr, .. } => visit(expr, ident),
+                YololExpr::Infix { lhs, rhs, .. } => visit(lhs, ident) || visit(rhs, ident),
+            }
+        }
+        visit(&self.expr, ident)
+    }
+
     /// Applies a prefix operation to a scalar.
     fn apply_prefix_op(&self, op: &PrefixOp) -> Self {
         Scalar {
@@ -142,7 +197,7 @@ pub struct Vector {
 impl From<Vec<Scalar>> for Vector {
     /// Converts scalars to a vector.
     fn from(scalars: Vec<Scalar>) -> Self {
-        Vector { scalars: scalars }
+        Vector { scalars }
     }
 }
 
@@ -155,7 +210,40 @@ impl Vector {
             let s: Scalar = format!("{}_{}", ident, i).parse().unwrap();
             scalars.push(s);
         }
-        Vector { scalars: scalars }
+        Vector { scalars }
+    }
+
+    /// Creates a vector by repeating a scalar.
+    pub fn repeat(scalar: &Scalar, len: usize) -> Self {
+        Vector {
+            scalars: vec![scalar.clone(); len],
+        }
+    }
+
+    /// Creates a vector of the integers in the range `start..end`.
+    pub fn range(start: i64, end: i64) -> Self {
+        Vector {
+            scalars: (start..end)
+                .map(|i| YololNumber::from_value(i).into())
+                .collect(),
+        }
+    }
+
+    /// Returns the number of scalars in a vector.
+    pub fn len(&self) -> usize {
+        self.scalars.len()
+    }
+
+    /// Gets a scalar from a vector by index.
+    pub fn get(&self, index: usize) -> Option<Scalar> {
+        self.scalars.get(index).cloned()
+    }
+
+    /// Creates a vector from the scalars in the range `start..end`.
+    pub fn slice(&self, start: usize, end: usize) -> Self {
+        Vector {
+            scalars: self.scalars[start..end].to_vec(),
+        }
     }
 
     /// Converts a vector to Yolol assign statements using a given identifier.
@@ -0,0 +1,45 @@
+use std::fmt;
+
+use crate::ast::Span;
+
+/// Represents a general warning.
+///
+/// Warnings do not stop a program from being transpiled, but point out code
+/// that may behave or cost more than expected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum YolkWarning {
+    DynamicIndex {
+        len: usize,
+        chars: usize,
+        span: Span,
+    },
+}
+
+impl YolkWarning {
+    /// Returns the span of the source code that caused a warning.
+    pub fn span(&self) -> Span {
+        match self {
+            Self::DynamicIndex { span, .. } => *span,
+        }
+    }
+
+    /// Returns the stable code of a warning.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::DynamicIndex { .. } => "W0001",
+        }
+    }
+}
+
+impl fmt::Display for YolkWarning {
+    /// Formats a warning as a string.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DynamicIndex { len, chars, span } => write!(
+                f,
+                "{}: dynamic index expands to {} term(s) and {} character(s)",
+                span, len, chars
+            ),
+        }
+    }
+}
@@ -2,11 +2,15 @@ use yoloxide::environment::{ContextMap, Environment};
 use yoloxide::execute_line;
 
 use yolk::error::YolkError;
-use yolk::{YolkProgram, YololProgram};
+use yolk::formatter::format;
+use yolk::layout::ChipLimits;
+use yolk::loader::Loader;
+use yolk::minifier::external_names;
+use yolk::{LayoutError, YolkProgram, YololProgram};
 
 use std::convert::TryInto;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 fn find_test_files() -> Vec<String> {
     let mut corpus = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -25,32 +29,124 @@ fn find_test_files() -> Vec<String> {
 
 fn yolol_to_env(program: YololProgram) -> Environment {
     let mut env = Environment::new("");
-    execute_line(&mut env, program.to_string());
+    for line in program.to_string().lines() {
+        execute_line(&mut env, line.to_string());
+    }
     env
 }
 
 #[test]
-fn test_correctness() -> Result<(), YolkError> {
+fn test_correctness() -> Result<(), Vec<YolkError>> {
     let test_files = find_test_files();
     for file in test_files {
         println!("case: {}", file);
-        let source = fs::read_to_string(file).unwrap();
-        let yolk: YolkProgram = source.parse()?;
+        let source = fs::read_to_string(&file).unwrap();
+        let yolk: YolkProgram = Loader::new().load(Path::new(&file), &source)?;
         let yolol: YololProgram = yolk.try_into()?;
         let optimized = yolol.optimize();
         let env = yolol_to_env(optimized);
+        assert_ne!(env.get_val("e").to_string(), "0");
         assert_eq!(env.get_val("n").to_string(), env.get_val("e").to_string());
     }
     Ok(())
 }
 
 #[test]
-fn test_idempotence() -> Result<(), YolkError> {
+fn test_minified_correctness() -> Result<(), Vec<YolkError>> {
     let test_files = find_test_files();
     for file in test_files {
         println!("case: {}", file);
-        let source = fs::read_to_string(file).unwrap();
-        let yolk: YolkProgram = source.parse()?;
+        let source = fs::read_to_string(&file).unwrap();
+        let yolk: YolkProgram = Loader::new().load(Path::new(&file), &source)?;
+        let keep = external_names(&yolk);
+        let yolol: YololProgram = yolk.try_into()?;
+        let (minified, _) = yolol.optimize().minify(&keep);
+        let env = yolol_to_env(minified);
+        assert_ne!(env.get_val("e").to_string(), "0");
+        assert_eq!(env.get_val("n").to_string(), env.get_val("e").to_string());
+    }
+    Ok(())
+}
+
+/// Runs a Yolol program after assigning its inputs.
+fn yolol_with_inputs_to_env(inputs: &str, program: YololProgram) -> Environment {
+    let mut env = Environment::new("");
+    execute_line(&mut env, inputs.to_string());
+    for line in program.to_string().lines() {
+        execute_line(&mut env, line.to_string());
+    }
+    env
+}
+
+#[test]
+fn test_simplification_semantics() -> Result<(), Vec<YolkError>> {
+    const EXPRS: &[&str] = &[
+        "(a + 1) + 2",
+        "(a - 1.5) + 2",
+        "(1 + a) - 3",
+        "(a - 1) - 2",
+        "(a + 0.5) - 0.5",
+        "(a * 2) * 3",
+        "(a * 0.5) * 0.5",
+        "a - a",
+        "(a + b) - (a + b)",
+        "1 / a - 1 / a",
+        "(abs(a) + 1) / (abs(a) + 1)",
+        "a / a",
+        "not(not(a < b))",
+        "not(not(a))",
+        "not(not(a)) and b",
+        "a or not(not(b))",
+        "--a",
+        "-(a - b)",
+        "2 * (a + 3)",
+        "(3 - a) * 4",
+        "(a - 1.5) * 4",
+        "0.5 * (a + 3)",
+        "a ^ 1",
+        "1 ^ a",
+        "1 ^ (1 / a)",
+        "a * 0",
+        "(a + b) * (a + b)",
+        "(1 / a) * 0",
+        "a + -1",
+        "a - -1",
+        "-a + b",
+        "a + -b",
+        "a - -b",
+    ];
+    const INPUTS: &[&str] = &[
+        "a=0 b=0",
+        "a=1 b=2",
+        "a=-3.5 b=0.25",
+        "a=2.001 b=-7",
+        "a=0.003 b=1",
+        "a=1.023 b=0",
+    ];
+    for expr in EXPRS.iter() {
+        let yolk: YolkProgram = format!("import a\nimport b\nlet n = {}", expr).parse()?;
+        let yolol: YololProgram = yolk.try_into()?;
+        let optimized = yolol.clone().optimize();
+        for inputs in INPUTS.iter() {
+            println!("case: {} with {} ({})", expr, inputs, optimized);
+            let expected = yolol_with_inputs_to_env(inputs, yolol.clone());
+            let actual = yolol_with_inputs_to_env(inputs, optimized.clone());
+            assert_eq!(
+                actual.get_val("n").to_string(),
+                expected.get_val("n").to_string()
+            );
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_idempotence() -> Result<(), Vec<YolkError>> {
+    let test_files = find_test_files();
+    for file in test_files {
+        println!("case: {}", file);
+        let source = fs::read_to_string(&file).unwrap();
+        let yolk: YolkProgram = Loader::new().load(Path::new(&file), &source)?;
         let yolol: YololProgram = yolk.try_into()?;
         let once = yolol.optimize();
         let twice = once.clone().optimize();
@@ -58,3 +154,234 @@ fn test_idempotence() -> Result<(), YolkError> {
     }
     Ok(())
 }
+
+#[test]
+fn test_layout_correctness() -> Result<(), Vec<YolkError>> {
+    let test_files = find_test_files();
+    let limits = ChipLimits {
+        width: 70,
+        lines: usize::MAX,
+    };
+    for file in test_files {
+        println!("case: {}", file);
+        let source = fs::read_to_string(&file).unwrap();
+        let yolk: YolkProgram = Loader::new().load(Path::new(&file), &source)?;
+        let yolol: YololProgram = yolk.try_into()?;
+        // Some programs have statements that are too long for any chip
+        let layout = match yolol.optimize().layout(&limits) {
+            Ok(layout) => layout,
+            Err(LayoutError::StmtTooLong { .. }) => continue,
+            Err(e) => panic!("{}", e),
+        };
+        let output = layout.to_string();
+        assert!(output.lines().all(|line| line.len() <= limits.width));
+        let mut env = Environment::new("");
+        for line in output.lines() {
+            execute_line(&mut env, line.to_string());
+        }
+        assert_eq!(env.get_val("n").to_string(), env.get_val("e").to_string());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_partition_correctness() -> Result<(), Vec<YolkError>> {
+    let test_files = find_test_files();
+    let limits = ChipLimits {
+        width: 70,
+        lines: 2,
+    };
+    for file in test_files {
+        println!("case: {}", file);
+        let source = fs::read_to_string(&file).unwrap();
+        let yolk: YolkProgram = Loader::new().load(Path::new(&file), &source)?;
+        let yolol: YololProgram = yolk.try_into()?;
+        // Some programs have statements that are too long for any chip
+        let partition = match yolol.optimize().partition(&limits) {
+            Ok(partition) => partition,
+            Err(LayoutError::StmtTooLong { .. }) => continue,
+            Err(e) => panic!("{}", e),
+        };
+        // Chips only share data fields, so they can run one after another in one environment
+        let mut env = Environment::new("");
+        for chip in partition.chips() {
+            assert!(chip.len() <= limits.lines);
+            for line in chip.to_string().lines() {
+                execute_line(&mut env, line.to_string());
+            }
+        }
+        let fields = partition.fields();
+        let n = fields.get("n").unwrap_or("n");
+        let e = fields.get("e").unwrap_or("e");
+        assert_eq!(env.get_val(n).to_string(), env.get_val(e).to_string());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_yolol_round_trip() -> Result<(), Vec<YolkError>> {
+    let test_files = find_test_files();
+    for file in test_files {
+        println!("case: {}", file);
+        let source = fs::read_to_string(&file).unwrap();
+        let yolk: YolkProgram = Loader::new().load(Path::new(&file), &source)?;
+        let yolol: YololProgram = yolk.try_into()?;
+        let optimized = yolol.clone().optimize();
+        let parsed: YololProgram = yolol.to_string().parse()?;
+        assert_eq!(parsed.to_string(), yolol.to_string());
+        // Negated literals (e.g. "-(2)") are formatted like negative literals,
+        // so only optimized programs parse back to the same tree
+        let parsed: YololProgram = optimized.to_string().parse()?;
+        assert_eq!(parsed, optimized);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_yolk_round_trip() -> Result<(), Vec<YolkError>> {
+    let mut test_files = find_test_files();
+    let mut lib = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    lib.push("tests/corpus/lib");
+    for path in fs::read_dir(lib).unwrap() {
+        test_files.push(path.unwrap().path().to_str().unwrap().to_string());
+    }
+    for file in test_files {
+        println!("case: {}", file);
+        let source = fs::read_to_string(&file).unwrap();
+        let yolk: YolkProgram = source.parse()?;
+        let parsed: YolkProgram = yolk.to_string().parse()?;
+        assert_eq!(parsed, yolk);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_format() -> Result<(), Vec<YolkError>> {
+    let test_files = find_test_files();
+    for file in test_files {
+        println!("case: {}", file);
+        let source = fs::read_to_string(&file).unwrap();
+        let formatted = format(&source)?;
+        assert_eq!(format(&formatted)?, formatted);
+        let yolk: YolkProgram = source.parse()?;
+        let parsed: YolkProgram = formatted.parse()?;
+        assert_eq!(parsed, yolk);
+    }
+    Ok(())
+}
+
+/// Generates pseudo-random sources from Yolk tokens.
+///
+/// A fixed seed keeps the generated sources the same between runs.
+fn generate_sources(count: usize) -> Vec<String> {
+    const TOKENS: &[&str] = &[
+        "import ",
+        "define ",
+        "let ",
+        "a",
+        "b",
+        "f",
+        "sum",
+        "abs",
+        "sqrt",
+        "tan",
+        "=",
+        "+",
+        "-",
+        "*",
+        "/",
+        "%",
+        "^",
+        "<",
+        "==",
+        "and",
+        "not",
+        "(",
+        ")",
+        "[",
+        "]",
+        ",",
+        "0",
+        "1",
+        "-1.5",
+        "999999999999999",
+        " ",
+        "\n",
+        "//",
+    ];
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut sources = Vec::new();
+    for _ in 0..count {
+        let mut source = String::new();
+        for _ in 0..40 {
+            state = state
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            source.push_str(TOKENS[(state >> 33) as usize % TOKENS.len()]);
+        }
+        sources.push(source);
+    }
+    sources
+}
+
+#[test]
+fn test_robustness() {
+    let mut sources: Vec<String> = vec![
+        "",
+        "\n\n\n",
+        "let",
+        "let a =",
+        "define f() = 0",
+        "define f(a) = a\nlet b = f(1, 2)",
+        "define g(a) = f(a)\ndefine f(a) = g(a)\nlet b = f(1)",
+        "let a = 99999999999999999999999999999999999",
+        "let a = 922337203685477.58 + 922337203685477.58",
+        "let a = 10 ^ 100",
+        "let a = (0 - 8) ^ 0.5",
+        "let a = tan(90)",
+        "let a = abs(1, 2)",
+        "let a = [[[1]]]",
+        "let a = [1, 2] + [1, 2, 3]",
+        "let é = 1",
+        "let a = 1 // é",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect();
+    sources.push(format!(
+        "let a = {}0{}",
+        "(".repeat(10_000),
+        ")".repeat(10_000)
+    ));
+    sources.push(format!(
+        "let a = {}0{}",
+        "[".repeat(10_000),
+        "]".repeat(10_000)
+    ));
+    sources.push(format!(
+        "let a = {}1, 2){}",
+        "abs(".repeat(60),
+        ")".repeat(59)
+    ));
+    sources.push(format!("import a\nlet x = a{}", "+a".repeat(10_000)));
+    sources.push(format!("import a\nlet x = a{}", "+a".repeat(255)));
+    sources.push(format!("a=b{}", "*b".repeat(10_000)));
+    sources.push(format!("a={}1", "-".repeat(10_000)));
+    sources.push(format!("a={}1", "not ".repeat(10_000)));
+    sources.push("a=99999999999999999999 b++ c+=d if e then f=1 end".to_string());
+    sources.extend(generate_sources(500));
+    for source in sources {
+        println!("case: {:?}", source);
+        if let Ok(yolk) = source.parse::<YolkProgram>() {
+            let parsed: YolkProgram = yolk.to_string().parse().unwrap();
+            assert_eq!(parsed, yolk);
+            let yolol: Result<YololProgram, Vec<YolkError>> = yolk.try_into();
+            if let Ok(yolol) = yolol {
+                yolol.optimize();
+            }
+        }
+        if let Ok(yolol) = source.parse::<YololProgram>() {
+            yolol.optimize();
+        }
+    }
+}